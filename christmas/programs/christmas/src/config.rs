@@ -0,0 +1,96 @@
+use anchor_lang::prelude::*;
+
+use crate::defs::*;
+use crate::errors::ChristmasError;
+use crate::state::ProgramState;
+use crate::utils::geo::validate_region;
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    #[account(
+        mut,
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        seeds = [b"state"],
+        bump = state.bump,
+        constraint = state.admin == signer.key(), // only the admin can update
+    )]
+    pub state: Account<'info, ProgramState>,
+    pub signer: Signer<'info>,
+}
+
+// Protocol policy set by the admin (0 limits are unlimited)
+#[account]
+pub struct Config {
+    pub max_mint_per_call: u64,     // tokens per `mint_to_market`
+    pub max_coupons_per_store: u64, // live coupons per store
+    pub fee_rate: u16,              // basis points of paid claims (10000 = 100%)
+    pub fee_recipient: Pubkey,      // receives fees (owner of the fee token account for SPL prices)
+    pub disabled_regions: [u8; REGION_BITMAP_SIZE], // 1 bit per `REGION_CODES` index
+    pub bump: u8,
+}
+
+impl Config {
+    pub fn len() -> usize {
+        DISCRIMINATOR_SIZE
+            + U64_SIZE // max_mint_per_call
+            + U64_SIZE // max_coupons_per_store
+            + U16_SIZE // fee_rate
+            + PUBKEY_SIZE // fee_recipient
+            + REGION_BITMAP_SIZE // disabled_regions
+            + BUMP_SIZE
+    }
+
+    // Checks `region` is a valid region code and is not disabled
+    pub fn validate_region(&self, region: &[u8; 3]) -> Result<()> {
+        validate_region(region)?;
+        let index = region_index(region).ok_or(ChristmasError::InvalidRegion)?;
+        require!(
+            self.disabled_regions[index / 8] & (1 << (index % 8)) == 0,
+            ChristmasError::RegionDisabled
+        );
+        Ok(())
+    }
+
+    pub fn set_region_enabled(&mut self, region: &[u8; 3], enabled: bool) -> Result<()> {
+        validate_region(region)?;
+        let index = region_index(region).ok_or(ChristmasError::InvalidRegion)?;
+        if enabled {
+            self.disabled_regions[index / 8] &= !(1 << (index % 8));
+        } else {
+            self.disabled_regions[index / 8] |= 1 << (index % 8);
+        }
+        Ok(())
+    }
+
+    pub fn validate_mint(&self, num_tokens: u64) -> Result<()> {
+        require!(
+            self.max_mint_per_call == 0 || num_tokens <= self.max_mint_per_call,
+            ChristmasError::MintLimitExceeded
+        );
+        Ok(())
+    }
+
+    // Checks the store can have another live coupon
+    pub fn validate_live_coupons(&self, live_coupons: u64) -> Result<()> {
+        require!(
+            self.max_coupons_per_store == 0 || live_coupons < self.max_coupons_per_store,
+            ChristmasError::MaxCouponsPerStoreExceeded
+        );
+        Ok(())
+    }
+
+    // Protocol fee taken out of `amount` (rounded down)
+    pub fn fee(&self, amount: u64) -> u64 {
+        (amount as u128 * self.fee_rate as u128 / MAX_FEE_RATE as u128) as u64
+    }
+}
+
+fn region_index(region: &[u8; 3]) -> Option<usize> {
+    REGION_CODES
+        .iter()
+        .position(|code| code.as_bytes() == region.as_ref())
+}
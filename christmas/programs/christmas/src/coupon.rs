@@ -2,12 +2,21 @@ use anchor_lang::prelude::*;
 use anchor_spl::associated_token::AssociatedToken;
 use anchor_spl::token::{Mint, Token, TokenAccount};
 
+use crate::config::Config;
 use crate::defs::*;
+use crate::errors::ChristmasError;
+use crate::loyalty::{LoyaltyProgram, PunchCard};
 use crate::market::RegionMarket;
+use crate::points::StorePoints;
+use crate::redemption::Redemption;
+use crate::role::StoreRole;
+use crate::state::ProgramState;
 use crate::store::Store;
 use crate::user::User;
+use crate::utils::utils::{days_to_byte_mask, epoch_days_from_date};
 
 #[derive(Accounts)]
+#[instruction(num_tokens: u64, redemption_id: u64)]
 pub struct RedeemCoupon<'info> {
     #[account(
         seeds = [b"coupon", mint.key().as_ref()],
@@ -28,10 +37,141 @@ pub struct RedeemCoupon<'info> {
         associated_token::authority = user,
     )]
     pub user_token_account: Account<'info, TokenAccount>,
+    #[account(
+        constraint = coupon.store == store.key(), // coupon belongs to store
+    )]
+    pub store: Account<'info, Store>,
+    pub store_signer: Option<Signer<'info>>, // required if the coupon is `cosigned_redeem`
+    pub store_signer_role: Option<Account<'info, StoreRole>>, // required if `store_signer` is a delegate
+    // receipt (`redemption_id` is chosen by the client, unique per coupon and user)
+    #[account(
+        init,
+        payer = payer,
+        seeds = [
+            b"redemption",
+            coupon.key().as_ref(),
+            signer.key().as_ref(),
+            &redemption_id.to_be_bytes()
+        ],
+        bump,
+        space = Redemption::len(),
+    )]
+    pub redemption: Account<'info, Redemption>,
+    // uses left on the holder's current token (required if `uses_per_token` > 1)
+    #[account(
+        init_if_needed,
+        payer = payer,
+        seeds = [b"uses", coupon.key().as_ref(), signer.key().as_ref()],
+        bump,
+        space = CouponUses::len(),
+    )]
+    pub uses: Option<Account<'info, CouponUses>>,
+    // required if the store has a loyalty program
+    #[account(
+        seeds = [b"loyalty", coupon.store.as_ref()],
+        bump = loyalty_program.bump,
+    )]
+    pub loyalty_program: Option<Account<'info, LoyaltyProgram>>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        seeds = [b"punch", coupon.store.as_ref(), signer.key().as_ref()],
+        bump,
+        space = PunchCard::len(),
+    )]
+    pub punch_card: Option<Account<'info, PunchCard>>,
+    // required when the punch card is full (the reward coupon is minted to the user)
+    #[account(mut)] // supply will change
+    pub reward_coupon: Option<Account<'info, Coupon>>,
+    #[account(mut)]
+    pub reward_mint: Option<Account<'info, Mint>>,
+    /// CHECK: The user's associated token account for `reward_mint`, created if it doesn't exist
+    #[account(mut)]
+    pub reward_user_token_account: Option<UncheckedAccount<'info>>,
+    pub associated_token_program: Option<Program<'info, AssociatedToken>>,
+    // required if the store has points (`store_points`)
+    #[account(
+        seeds = [b"points", coupon.store.as_ref()],
+        bump = store_points.bump,
+    )]
+    pub store_points: Option<Account<'info, StorePoints>>,
+    #[account(mut)]
+    pub points_mint: Option<Account<'info, Mint>>,
+    #[account(mut)]
+    pub user_points_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(
+        seeds = [b"state"],
+        bump = state.bump,
+    )]
+    pub state: Account<'info, ProgramState>, // checked for `paused`
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>, // checked for disabled regions
     pub signer: Signer<'info>,
     #[account(mut)]
     pub payer: Signer<'info>,
     pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct TransferCoupon<'info> {
+    #[account(
+        seeds = [b"coupon", mint.key().as_ref()],
+        bump = coupon.bump,
+        constraint = coupon.mint == mint.key()  // coupon belongs to mint
+    )]
+    pub coupon: Account<'info, Coupon>,
+    pub mint: Account<'info, Mint>,
+    #[account(
+        seeds = [b"user", signer.key().as_ref()], // signer must be the sender
+        bump = user.bump,
+    )]
+    pub user: Account<'info, User>,
+    #[account(
+        mut, // balance will change
+        associated_token::mint = mint,
+        associated_token::authority = user,
+    )]
+    pub user_token_account: Account<'info, TokenAccount>,
+    /// CHECK: Uses left on the sender's current token (required if `uses_per_token` > 1), may not
+    /// have been created if the sender never redeemed
+    #[account(
+        seeds = [b"uses", coupon.key().as_ref(), signer.key().as_ref()],
+        bump,
+    )]
+    pub uses: Option<UncheckedAccount<'info>>,
+    /// CHECK: Recipient wallet, only used to derive the recipient user
+    pub recipient: UncheckedAccount<'info>,
+    // create recipient user if needed when receiving coupon
+    #[account(
+        init_if_needed,
+        payer = payer,
+        seeds = [b"user", recipient.key().as_ref()],
+        bump,
+        space = User::len(),
+    )]
+    pub recipient_user: Account<'info, User>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = mint,
+        associated_token::authority = recipient_user, // note: user not recipient
+    )]
+    pub recipient_token_account: Account<'info, TokenAccount>,
+    #[account(
+        seeds = [b"state"],
+        bump = state.bump,
+    )]
+    pub state: Account<'info, ProgramState>, // checked for `paused`
+    pub signer: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
 }
 
 #[derive(Accounts)]
@@ -42,14 +182,16 @@ pub struct RedeemCoupon<'info> {
     uri: String,
     valid_from: u64,
     valid_to: u64,
+    options: CouponOptions,
+    terms: CouponTerms,
 )]
 pub struct CreateCoupon<'info> {
     #[account(
         init_if_needed,
         payer = payer,
         mint::decimals = 0,
-        mint::authority = signer,
-        mint::freeze_authority = signer,
+        mint::authority = coupon, // all minting is through the program (owner or delegates)
+        mint::freeze_authority = coupon, // program freezes/thaws `soulbound` coupons
     )]
     pub mint: Account<'info, Mint>,
     #[account(
@@ -76,11 +218,20 @@ pub struct CreateCoupon<'info> {
     )]
     pub region_market_token_account: Account<'info, TokenAccount>,
     #[account(
-        seeds = [b"store", signer.key().as_ref(), &store.id.to_be_bytes()],
-        constraint = store.owner == signer.key(),
-        bump,
+        mut, // live_coupons will change
     )]
     pub store: Account<'info, Store>,
+    pub role: Option<Account<'info, StoreRole>>, // required if the signer is not the owner
+    #[account(
+        seeds = [b"state"],
+        bump = state.bump,
+    )]
+    pub state: Account<'info, ProgramState>, // checked for `paused`
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>, // checked for region and coupon limits
     pub signer: Signer<'info>,
     #[account(mut)]
     pub payer: Signer<'info>,
@@ -89,6 +240,246 @@ pub struct CreateCoupon<'info> {
     pub associated_token_program: Program<'info, AssociatedToken>,
 }
 
+#[derive(Accounts)]
+pub struct UpdateCoupon<'info> {
+    #[account(
+        mut,
+        seeds = [b"coupon", coupon.mint.as_ref()],
+        bump = coupon.bump,
+        constraint = coupon.is_update_authority(&store, signer.key()), // only the update authority can update
+    )]
+    pub coupon: Account<'info, Coupon>,
+    #[account(address = coupon.store)]
+    pub store: Account<'info, Store>,
+    pub signer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseCoupon<'info> {
+    #[account(
+        mut,
+        seeds = [b"coupon", mint.key().as_ref()],
+        bump = coupon.bump,
+        constraint = coupon.mint == mint.key(), // coupon is for this mint
+        constraint = coupon.store == store.key(), // coupon belongs to store
+        close = store_owner, // return rent to the store owner
+    )]
+    pub coupon: Account<'info, Coupon>,
+    #[account(mut)] // supply will change
+    pub mint: Account<'info, Mint>,
+    #[account(
+        seeds = [b"market", region_market.region.as_ref()],
+        bump = region_market.bump,
+        constraint = region_market.region == coupon.region  // coupon is for this region
+    )]
+    pub region_market: Account<'info, RegionMarket>,
+    #[account(
+        mut,
+        associated_token::mint = mint,
+        associated_token::authority = region_market,
+    )]
+    pub region_market_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut, // live_coupons will change
+    )]
+    pub store: Account<'info, Store>,
+    /// CHECK: Only receives the rent, must be the store owner
+    #[account(
+        mut,
+        address = store.owner,
+    )]
+    pub store_owner: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"state"],
+        bump = state.bump,
+    )]
+    pub state: Account<'info, ProgramState>, // checked for `paused`
+    pub signer: Signer<'info>, // anyone can close an expired coupon
+    pub token_program: Program<'info, Token>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateMintAuthority<'info> {
+    /// CHECK: Coupon of the mint, loaded in the instruction (legacy coupons are grown to the current layout first)
+    #[account(
+        mut,
+        seeds = [b"coupon", mint.key().as_ref()],
+        bump,
+    )]
+    pub coupon: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        mint::authority = signer, // coupons created when the merchant was the mint authority
+    )]
+    pub mint: Account<'info, Mint>,
+    #[account(mut)] // pays the rent for growing legacy coupons
+    pub signer: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[event]
+pub struct CouponUpdateAuthorityChanged {
+    pub coupon: Pubkey,
+    pub previous_update_authority: Pubkey,
+    pub update_authority: Pubkey,
+}
+
+#[event]
+pub struct CouponUpdated {
+    pub coupon: Pubkey,
+    pub name: String,
+    pub geohash: [u8; 6],
+    pub uri: String,
+    pub valid_from: u64,
+    pub valid_to: u64,
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct CouponOptions {
+    pub cosigned_redeem: bool, // redemption must also be signed by the store owner
+    pub max_claims_per_user: u64, // 0 for unlimited
+    pub max_supply: u32,       // 0 for unlimited (up to u32::MAX)
+    pub transferable: bool,    // users can gift coupons to other users
+    pub soulbound: bool,       // user token accounts are frozen except during redemption
+    pub price: u64,            // per token, 0 for free
+    pub price_mint: Pubkey,    // Pubkey::default() for lamports
+    pub uses_per_token: u32,   // 0 or 1 for single use
+    pub schedule: CouponSchedule,
+}
+
+impl CouponOptions {
+    pub fn len() -> usize {
+        BOOL_SIZE // cosigned_redeem
+            + U64_SIZE // max_claims_per_user
+            + SUPPLY_SIZE // max_supply
+            + BOOL_SIZE // transferable
+            + BOOL_SIZE // soulbound
+            + U64_SIZE // price
+            + PUBKEY_SIZE // price_mint
+            + U32_SIZE // uses_per_token
+            + CouponSchedule::len()
+    }
+}
+
+// Weekly schedule for redemption (eg. lunch only, weekends only)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct CouponSchedule {
+    pub weekdays: u8,      // bit 0 is Sunday ... bit 6 is Saturday, 0 for no schedule
+    pub start_minute: u16, // minutes since local midnight (inclusive)
+    pub end_minute: u16, // exclusive (1440 for midnight), wraps past midnight if before `start_minute`
+    pub utc_offset_minutes: i16, // timezone of the store (eg. 480 for UTC+8)
+}
+
+impl CouponSchedule {
+    pub fn len() -> usize {
+        U8_SIZE // weekdays
+            + U16_SIZE // start_minute
+            + U16_SIZE // end_minute
+            + U16_SIZE // utc_offset_minutes
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.weekdays < 1 << 7
+                && self.start_minute < MINUTES_PER_DAY
+                && self.end_minute <= MINUTES_PER_DAY
+                && (self.weekdays == 0 || self.start_minute != self.end_minute)
+                && self.utc_offset_minutes.unsigned_abs() <= MAX_UTC_OFFSET_MINUTES,
+            ChristmasError::InvalidSchedule
+        );
+        Ok(())
+    }
+
+    pub fn contains(&self, timestamp_ms: u64) -> bool {
+        if self.weekdays == 0 {
+            return true;
+        }
+        let local_minutes = (timestamp_ms / 60_000) as i64 + self.utc_offset_minutes as i64;
+        let days = local_minutes.div_euclid(MINUTES_PER_DAY as i64);
+        let minute = local_minutes.rem_euclid(MINUTES_PER_DAY as i64) as u16;
+        let (days, in_window) = if self.start_minute <= self.end_minute {
+            (
+                days,
+                minute >= self.start_minute && minute < self.end_minute,
+            )
+        } else if minute >= self.start_minute {
+            (days, true)
+        } else {
+            // after midnight, in the window that started the day before
+            (days - 1, minute < self.end_minute)
+        };
+        let weekday = (days + 4).rem_euclid(7); // 1 jan 1970 is a Thursday
+        in_window && self.weekdays & (1 << weekday) != 0
+    }
+}
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Default, PartialEq, Eq, Debug)]
+pub enum DiscountType {
+    #[default]
+    Percentage, // `value` in basis points (10000 = 100%)
+    FixedAmount, // `value` in the smallest unit of `currency` (eg. cents)
+}
+
+// Fixed at creation so claimed coupons can't be changed by the merchant (unlike the `uri` metadata)
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Default)]
+pub struct CouponTerms {
+    pub discount_type: DiscountType,
+    pub value: u64,
+    pub currency: [u8; 3],    // ISO 4217 (eg. SGD)
+    pub min_spend: u64,       // in the smallest unit of `currency`, 0 for none
+    pub terms_hash: [u8; 32], // sha256 of the terms document
+}
+
+impl CouponTerms {
+    pub fn len() -> usize {
+        U8_SIZE // discount_type
+            + U64_SIZE // value
+            + CURRENCY_SIZE
+            + U64_SIZE // min_spend
+            + HASH_SIZE // terms_hash
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        require!(
+            self.discount_type != DiscountType::Percentage || self.value <= 10_000,
+            ChristmasError::InvalidTerms
+        );
+        Ok(())
+    }
+}
+
+#[account]
+pub struct CouponUses {
+    pub remaining: u32, // 0 if the holder has not started a token
+    pub bump: u8,
+}
+
+impl CouponUses {
+    pub fn len() -> usize {
+        DISCRIMINATOR_SIZE + U32_SIZE + BUMP_SIZE
+    }
+
+    // Consumes `num_uses` (starting new tokens as needed) and returns the number of used up tokens to burn
+    pub fn consume(&mut self, uses_per_token: u32, num_uses: u64) -> u64 {
+        let uses_per_token = uses_per_token as u64;
+        let remaining = self.remaining as u64;
+        if num_uses <= remaining {
+            self.remaining = (remaining - num_uses) as u32;
+            return (num_uses > 0 && self.remaining == 0) as u64;
+        }
+        let started = (remaining > 0) as u64; // current token is used up
+        let extra_uses = num_uses - remaining;
+        let partial_uses = extra_uses % uses_per_token;
+        self.remaining = if partial_uses > 0 {
+            (uses_per_token - partial_uses) as u32
+        } else {
+            0
+        };
+        started + extra_uses / uses_per_token
+    }
+}
+
 #[account]
 pub struct Coupon {
     /*
@@ -98,7 +489,7 @@ pub struct Coupon {
     TODO: Move to Token 22 and store it in the Mint Account
     https://github.com/solana-labs/solana-program-library/tree/master/token-metadata/interface
     */
-    pub update_authority: Pubkey,
+    pub update_authority: Pubkey, // the store (its current owner) unless delegated
     pub mint: Pubkey,
     pub name: String,
     pub uri: String, // to metadata
@@ -117,12 +508,42 @@ pub struct Coupon {
     pub valid_to_hash: [u8; 32],
     pub datehash_overflow: bool,
 
+    // options (after memcmp block to keep its offset)
+    pub options: CouponOptions,
+    pub terms: CouponTerms,
+
     // bump
     pub bump: u8, // TODO: might not need once move to Mint Account
 }
 
 impl Coupon {
-    fn len() -> usize {
+    // The store owner can always update its coupons, as can a delegated `update_authority`
+    pub fn is_update_authority(&self, store: &Store, signer: Pubkey) -> bool {
+        signer == store.owner || signer == self.update_authority
+    }
+
+    pub fn set_validity(&mut self, valid_from: u64, valid_to: u64) {
+        self.valid_from = valid_from;
+        self.valid_to = valid_to;
+
+        // calculate date hashes (keep memcmp block consistent with the dates)
+        let valid_from_days: u64 = epoch_days_from_date(valid_from);
+        let valid_to_days: u64 = epoch_days_from_date(valid_to);
+        self.datehash_overflow = valid_from_days > valid_to_days;
+        self.valid_from_hash = days_to_byte_mask(valid_from_days);
+        self.valid_to_hash = days_to_byte_mask(valid_to_days);
+    }
+
+    pub fn validate_period(&self, timestamp_ms: u64) -> Result<()> {
+        require!(
+            timestamp_ms >= self.valid_from,
+            ChristmasError::CouponNotYetValid
+        );
+        require!(timestamp_ms <= self.valid_to, ChristmasError::CouponExpired);
+        Ok(())
+    }
+
+    pub fn len() -> usize {
         DISCRIMINATOR_SIZE
             + PUBKEY_SIZE // update_authority
             + PUBKEY_SIZE // mint
@@ -140,7 +561,22 @@ impl Coupon {
             + DATE_HASH_SIZE // valid_from_hash
             + DATE_HASH_SIZE // valid_to_hash
             + DATE_HASH_OVERFLOW_SIZE
+            // options
+            + CouponOptions::len()
+            // terms
+            + CouponTerms::len()
             // bump
             + BUMP_SIZE
     }
+
+    // Coupons created before `options` and `terms` were added
+    pub fn legacy_len() -> usize {
+        Self::len() - CouponOptions::len() - CouponTerms::len()
+    }
+
+    // Moves the bump of a legacy coupon grown to `len` (leaving zeroed options and terms, ie. none)
+    pub fn migrate_legacy(data: &mut [u8]) {
+        data[Self::len() - BUMP_SIZE] = data[Self::legacy_len() - BUMP_SIZE];
+        data[Self::legacy_len() - BUMP_SIZE] = 0;
+    }
 }
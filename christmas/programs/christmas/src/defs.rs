@@ -14,7 +14,10 @@ pub const TWO_FACTOR_SIZE: usize = U8_SIZE * 32; // 256 bit
 pub const REGION_SIZE: usize = 3; // 3 character country code
 pub const COUPON_NAME_SIZE: usize = STRING_PREFIX_SIZE + 36;
 pub const STORE_NAME_SIZE: usize = STRING_PREFIX_SIZE + 36;
+pub const REFERENCE_SIZE: usize = STRING_PREFIX_SIZE + 32; // merchant reference (eg. POS order id)
 pub const DATE_SIZE: usize = U64_SIZE; // unix timestamp
+pub const CURRENCY_SIZE: usize = 3; // ISO 4217 currency code
+pub const HASH_SIZE: usize = 32; // sha256
 
 pub const DATE_HASH_SIZE: usize = 32; // 32 * 8 = 256 days per epoch since 1 jan 2024
 pub const DATE_HASH_OVERFLOW_SIZE: usize = BOOL_SIZE;
@@ -22,7 +25,11 @@ pub const HAS_SUPPLY_SIZE: usize = BOOL_SIZE;
 pub const SUPPLY_SIZE: usize = U32_SIZE;
 pub const DAYS_SINCE_1_JAN_2024: u64 = 19722; // Math.floor(new Date(2024, 0, 1).getTime() / MS_PER_DAY)
 pub const MS_PER_DAY: u64 = 1000 * 60 * 60 * 24;
+pub const MINUTES_PER_DAY: u16 = 60 * 24;
+pub const MAX_UTC_OFFSET_MINUTES: u16 = 14 * 60; // UTC-12 to UTC+14
 pub const DATE_HASH_BITS: u64 = DATE_HASH_SIZE as u64 * 8;
+pub const MAX_FEE_RATE: u16 = 10_000; // basis points (100%)
+pub const REGION_BITMAP_SIZE: usize = 32; // 256 bits (>= `REGION_CODES.len()`)
 
 pub const REGION_CODES: &'static [&'static str] = &[
     "@@@", "AFG", "ALB", "DZA", "ASM", "AND", "AGO", "AIA", "ATA", "ATG", "ARG", "ARM", "ABW", "AUS",
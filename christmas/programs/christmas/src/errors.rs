@@ -0,0 +1,97 @@
+use anchor_lang::prelude::*;
+
+#[error_code]
+pub enum ChristmasError {
+    #[msg("Invalid region code")]
+    InvalidRegion,
+    #[msg("Name exceeds maximum length")]
+    NameTooLong,
+    #[msg("URI exceeds maximum length")]
+    UriTooLong,
+    #[msg("Coupon supply overflow")]
+    SupplyOverflow,
+    #[msg("Region market does not match coupon region")]
+    MarketMismatch,
+    #[msg("Invalid date range (valid_from must not be after valid_to)")]
+    InvalidDateRange,
+    #[msg("User already exists")]
+    UserAlreadyExists,
+    #[msg("Store already exists")]
+    StoreAlreadyExists,
+    #[msg("Coupon already exists")]
+    CouponAlreadyExists,
+    #[msg("Store still has live coupons")]
+    StoreHasLiveCoupons,
+    #[msg("Not enough coupon supply")]
+    InsufficientSupply,
+    #[msg("Coupon has not expired")]
+    CouponNotExpired,
+    #[msg("Coupon is not yet valid")]
+    CouponNotYetValid,
+    #[msg("Coupon has expired")]
+    CouponExpired,
+    #[msg("Redemption must be signed by the store owner")]
+    StoreSignatureRequired,
+    #[msg("Reference exceeds maximum length")]
+    ReferenceTooLong,
+    #[msg("Redemption does not belong to store")]
+    RedemptionStoreMismatch,
+    #[msg("Claim limit per user exceeded")]
+    ClaimLimitExceeded,
+    #[msg("Coupon max supply exceeded")]
+    MaxSupplyExceeded,
+    #[msg("Coupon is not transferable")]
+    CouponNotTransferable,
+    #[msg("Coupon price overflow")]
+    PriceOverflow,
+    #[msg("Missing or invalid payment token accounts")]
+    InvalidPaymentAccounts,
+    #[msg("Insufficient treasury balance")]
+    InsufficientTreasuryBalance,
+    #[msg("Invalid coupon terms")]
+    InvalidTerms,
+    #[msg("Not enough coupons to redeem")]
+    InsufficientCouponBalance,
+    #[msg("Loyalty threshold must be more than 0")]
+    InvalidLoyaltyThreshold,
+    #[msg("Missing or invalid loyalty accounts")]
+    InvalidLoyaltyAccounts,
+    #[msg("Points overflow")]
+    PointsOverflow,
+    #[msg("Missing or invalid points accounts")]
+    InvalidPointsAccounts,
+    #[msg("Store has no points reward")]
+    PointsRewardNotSet,
+    #[msg("Invalid coupon schedule")]
+    InvalidSchedule,
+    #[msg("Coupon can't be redeemed at this time")]
+    CouponNotInSchedule,
+    #[msg("Signer is not the store owner or a permitted delegate")]
+    Unauthorized,
+    #[msg("Invalid permissions")]
+    InvalidPermissions,
+    #[msg("Program is paused")]
+    ProgramPaused,
+    #[msg("Region is disabled")]
+    RegionDisabled,
+    #[msg("Mint limit per call exceeded")]
+    MintLimitExceeded,
+    #[msg("Store has reached the max number of coupons")]
+    MaxCouponsPerStoreExceeded,
+    #[msg("Fee rate must be at most 10000 basis points")]
+    InvalidFeeRate,
+    #[msg("Redemption must be closed to its payer")]
+    RedemptionPayerMismatch,
+    #[msg("Missing coupon uses account")]
+    MissingUsesAccount,
+    #[msg("Number of tokens must be more than 0")]
+    InvalidNumTokens,
+    #[msg("Account is already migrated")]
+    AlreadyMigrated,
+    #[msg("Fee recipient is required to charge a fee")]
+    InvalidFeeRecipient,
+    #[msg("Treasury token accounts must be empty")]
+    TreasuryNotEmpty,
+    #[msg("Partly used coupon can't be transferred")]
+    CouponPartlyUsed,
+}
@@ -1,17 +1,40 @@
 use anchor_lang::prelude::*;
+mod config;
 mod coupon;
 mod defs;
+mod errors;
+mod loyalty;
 mod market;
+mod points;
+mod redemption;
+mod role;
 mod state;
 mod store;
+#[cfg(test)]
+mod test_utils;
+mod treasury;
 mod user;
 mod utils;
-use anchor_spl::token::{burn, mint_to, transfer, Burn, MintTo, Transfer};
+use anchor_lang::system_program;
+use anchor_lang::Discriminator;
+use anchor_spl::associated_token::{create_idempotent, get_associated_token_address, Create};
+use anchor_spl::token::spl_token::instruction::AuthorityType;
+use anchor_spl::token::{
+    burn, close_account, freeze_account, mint_to, set_authority, thaw_account, transfer, Burn,
+    CloseAccount, FreezeAccount, MintTo, SetAuthority, ThawAccount, TokenAccount, Transfer,
+};
+use config::*;
 use coupon::*;
+use loyalty::*;
 use market::*;
+use points::*;
+use redemption::*;
+use role::*;
+use solana_program::program_option::COption;
 use solana_program::rent::Rent;
 use state::*;
 use store::*;
+use treasury::*;
 use user::*;
 use utils::utils::*;
 
@@ -21,8 +44,11 @@ declare_id!("B2ejsK7m3eYPerru92hS73Gx7sQ7J83DKoLHGwn6pg5v");
 pub mod christmas {
 
     use crate::{
-        defs::{COUPON_NAME_SIZE, STORE_NAME_SIZE, STRING_PREFIX_SIZE, URI_SIZE},
-        utils::geo::code_bytes_to_country,
+        defs::{
+            COUPON_NAME_SIZE, DISCRIMINATOR_SIZE, MAX_FEE_RATE, REFERENCE_SIZE, STORE_NAME_SIZE,
+            STRING_PREFIX_SIZE, URI_SIZE,
+        },
+        errors::ChristmasError,
     };
 
     use super::*;
@@ -34,24 +60,120 @@ pub mod christmas {
             // set initialized
             ctx.accounts.program_state.is_initialized = true;
             ctx.accounts.program_state.store_counter = 0;
+            ctx.accounts.program_state.active_stores = 0;
+            ctx.accounts.program_state.admin = ctx.accounts.signer.key();
+            ctx.accounts.program_state.paused = false;
             ctx.accounts.program_state.bump = *ctx.bumps.get("program_state").unwrap();
         }
+        // config is created with no limits, fees or disabled regions (existing deployments call
+        // `initialize` again after `migrate_program_state`)
+        if ctx.accounts.config.bump == 0 {
+            ctx.accounts.config.bump = *ctx.bumps.get("config").unwrap();
+        }
+        Ok(())
+    }
+
+    pub fn set_admin(ctx: Context<UpdateProgramState>, admin: Pubkey) -> Result<()> {
+        ctx.accounts.program_state.admin = admin;
+        Ok(())
+    }
+
+    pub fn set_paused(ctx: Context<UpdateProgramState>, paused: bool) -> Result<()> {
+        ctx.accounts.program_state.paused = paused;
+        Ok(())
+    }
+
+    pub fn migrate_program_state(ctx: Context<MigrateProgramState>) -> Result<()> {
+        // only program state created before the admin was added can't be loaded
+        let program_state = ctx.accounts.program_state.to_account_info();
+        require!(
+            program_state.owner == ctx.program_id
+                && program_state.data_len() == ProgramState::legacy_len(),
+            ChristmasError::AlreadyMigrated
+        );
+        let (is_initialized, store_counter, bump) = <(bool, u64, u8)>::deserialize(
+            &mut &program_state.try_borrow_data()?[DISCRIMINATOR_SIZE..],
+        )?;
+
+        realloc_with_rent(
+            &program_state,
+            ProgramState::len(),
+            &ctx.accounts.signer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+
+        // stores couldn't be closed before, so all of them are active
+        ProgramState {
+            is_initialized,
+            store_counter,
+            active_stores: store_counter,
+            admin: ctx.accounts.signer.key(),
+            paused: false,
+            bump,
+        }
+        .try_serialize(&mut &mut program_state.try_borrow_mut_data()?[..])?;
+
+        Ok(())
+    }
+
+    pub fn set_config(
+        ctx: Context<UpdateConfig>,
+        max_mint_per_call: u64,
+        max_coupons_per_store: u64,
+        fee_rate: u16,
+        fee_recipient: Pubkey,
+    ) -> Result<()> {
+        require!(fee_rate <= MAX_FEE_RATE, ChristmasError::InvalidFeeRate);
+        require!(
+            fee_rate == 0 || fee_recipient != Pubkey::default(),
+            ChristmasError::InvalidFeeRecipient
+        );
+
+        ctx.accounts.config.max_mint_per_call = max_mint_per_call;
+        ctx.accounts.config.max_coupons_per_store = max_coupons_per_store;
+        ctx.accounts.config.fee_rate = fee_rate;
+        ctx.accounts.config.fee_recipient = fee_recipient;
         Ok(())
     }
 
+    pub fn set_region_enabled(
+        ctx: Context<UpdateConfig>,
+        region: [u8; 3],
+        enabled: bool,
+    ) -> Result<()> {
+        ctx.accounts.config.set_region_enabled(&region, enabled)
+    }
+
     pub fn create_user(ctx: Context<CreateUser>, region: [u8; 3], uri: String) -> Result<()> {
-        // check valid region
-        code_bytes_to_country(&region).unwrap();
+        // user can only be created once (use `update_user` to make changes)
+        require!(
+            ctx.accounts.user.bump == 0,
+            ChristmasError::UserAlreadyExists
+        );
+
+        // check valid and enabled region
+        ctx.accounts.config.validate_region(&region)?;
 
         ctx.accounts.user.region = region;
         ctx.accounts.user.bump = *ctx.bumps.get("user").unwrap();
-        ctx.accounts.user.uri = pad_string(&uri, URI_SIZE - STRING_PREFIX_SIZE);
+        ctx.accounts.user.uri = pad_string(
+            &uri,
+            URI_SIZE - STRING_PREFIX_SIZE,
+            ChristmasError::UriTooLong,
+        )?;
         Ok(())
     }
 
     pub fn update_user(ctx: Context<UpdateUser>, region: [u8; 3], uri: String) -> Result<()> {
+        // check valid and enabled region
+        ctx.accounts.config.validate_region(&region)?;
+
         ctx.accounts.user.region = region;
-        ctx.accounts.user.uri = pad_string(&uri, URI_SIZE - STRING_PREFIX_SIZE);
+        ctx.accounts.user.uri = pad_string(
+            &uri,
+            URI_SIZE - STRING_PREFIX_SIZE,
+            ChristmasError::UriTooLong,
+        )?;
         Ok(())
     }
 
@@ -63,23 +185,175 @@ pub mod christmas {
         geohash: [u8; 6],
         uri: String,
     ) -> Result<()> {
-        // check valid region
-        code_bytes_to_country(&region).unwrap();
+        // check program is not paused
+        require!(!ctx.accounts.state.paused, ChristmasError::ProgramPaused);
+
+        // store can only be created once
+        require!(
+            ctx.accounts.store.bump == 0,
+            ChristmasError::StoreAlreadyExists
+        );
+
+        // check valid and enabled region
+        ctx.accounts.config.validate_region(&region)?;
 
         ctx.accounts.store.id = id; // unique (can have same name but different id)
-        ctx.accounts.store.name = pad_string(&name, STORE_NAME_SIZE - STRING_PREFIX_SIZE);
+        ctx.accounts.store.name = pad_string(
+            &name,
+            STORE_NAME_SIZE - STRING_PREFIX_SIZE,
+            ChristmasError::NameTooLong,
+        )?;
         ctx.accounts.store.region = region;
         ctx.accounts.store.geohash = geohash;
-        ctx.accounts.store.uri = pad_string(&uri, URI_SIZE - STRING_PREFIX_SIZE);
+        ctx.accounts.store.uri = pad_string(
+            &uri,
+            URI_SIZE - STRING_PREFIX_SIZE,
+            ChristmasError::UriTooLong,
+        )?;
         ctx.accounts.store.owner = ctx.accounts.signer.key();
+        ctx.accounts.store.live_coupons = 0;
+        ctx.accounts.store.pending_owner = Pubkey::default();
+        ctx.accounts.store.role_epoch = 0;
         ctx.accounts.store.bump = *ctx.bumps.get("store").unwrap();
 
         // increment `store_counter` (Note: there is a max of 2^64 store)
         ctx.accounts.state.store_counter = ctx.accounts.state.store_counter.saturating_add(1);
+        ctx.accounts.state.active_stores = ctx.accounts.state.active_stores.saturating_add(1);
+
+        Ok(())
+    }
+
+    pub fn update_store(
+        ctx: Context<UpdateStore>,
+        name: String,
+        region: [u8; 3],
+        geohash: [u8; 6],
+        uri: String,
+    ) -> Result<()> {
+        // check valid and enabled region
+        ctx.accounts.config.validate_region(&region)?;
+
+        ctx.accounts.store.name = pad_string(
+            &name,
+            STORE_NAME_SIZE - STRING_PREFIX_SIZE,
+            ChristmasError::NameTooLong,
+        )?;
+        ctx.accounts.store.region = region;
+        ctx.accounts.store.geohash = geohash;
+        ctx.accounts.store.uri = pad_string(
+            &uri,
+            URI_SIZE - STRING_PREFIX_SIZE,
+            ChristmasError::UriTooLong,
+        )?;
+
+        emit!(StoreUpdated {
+            store: ctx.accounts.store.key(),
+            name: ctx.accounts.store.name.clone(),
+            region,
+            geohash,
+            uri: ctx.accounts.store.uri.clone(),
+        });
+
+        Ok(())
+    }
+
+    pub fn propose_store_owner(ctx: Context<ProposeStoreOwner>, new_owner: Pubkey) -> Result<()> {
+        // Pubkey::default() cancels the proposal
+        ctx.accounts.store.pending_owner = new_owner;
+        Ok(())
+    }
+
+    pub fn accept_store_owner(ctx: Context<AcceptStoreOwner>) -> Result<()> {
+        let previous_owner = ctx.accounts.store.owner;
+        ctx.accounts.store.owner = ctx.accounts.signer.key();
+        ctx.accounts.store.pending_owner = Pubkey::default();
+
+        // void the roles granted by the previous owner
+        ctx.accounts.store.role_epoch = ctx.accounts.store.role_epoch.wrapping_add(1);
+
+        emit!(StoreOwnerChanged {
+            store: ctx.accounts.store.key(),
+            previous_owner,
+            owner: ctx.accounts.store.owner,
+        });
+
+        Ok(())
+    }
+
+    pub fn migrate_store(ctx: Context<MigrateStore>, live_coupons: u64) -> Result<()> {
+        // only stores created before the coupon counter was added can't be loaded
+        let store = ctx.accounts.store.to_account_info();
+        require!(
+            store.owner == ctx.program_id
+                && store.data_len() == Store::legacy_len()
+                && store.try_borrow_data()?[..DISCRIMINATOR_SIZE] == Store::DISCRIMINATOR,
+            ChristmasError::AlreadyMigrated
+        );
+        let (id, name, region, geohash, uri, owner, bump) =
+            <(u64, String, [u8; 3], [u8; 6], String, Pubkey, u8)>::deserialize(
+                &mut &store.try_borrow_data()?[DISCRIMINATOR_SIZE..],
+            )?;
+
+        realloc_with_rent(
+            &store,
+            Store::len(),
+            &ctx.accounts.signer.to_account_info(),
+            &ctx.accounts.system_program.to_account_info(),
+        )?;
+
+        // the admin counts the store's (unclosed) coupons off-chain
+        Store {
+            id,
+            name,
+            region,
+            geohash,
+            uri,
+            owner,
+            live_coupons,
+            pending_owner: Pubkey::default(),
+            role_epoch: 0,
+            bump,
+        }
+        .try_serialize(&mut &mut store.try_borrow_mut_data()?[..])?;
+
+        Ok(())
+    }
+
+    pub fn close_store<'info>(ctx: Context<'_, '_, '_, 'info, CloseStore<'info>>) -> Result<()> {
+        // check program is not paused
+        require!(!ctx.accounts.state.paused, ChristmasError::ProgramPaused);
+
+        // coupons reference the store, close them first
+        require!(
+            ctx.accounts.store.live_coupons == 0,
+            ChristmasError::StoreHasLiveCoupons
+        );
+
+        // the treasury's token accounts are passed as remaining accounts, SPL token balances
+        // can't be withdrawn once the store is closed (use `withdraw_treasury` beforehand)
+        let treasury = ctx.accounts.treasury.to_account_info();
+        for account in ctx.remaining_accounts {
+            let token_account = Account::<TokenAccount>::try_from(account)?;
+            require_keys_eq!(
+                token_account.owner,
+                treasury.key(),
+                ChristmasError::InvalidPaymentAccounts
+            );
+            require!(token_account.amount == 0, ChristmasError::TreasuryNotEmpty);
+        }
+
+        // sweep the treasury's lamports to the owner
+        if treasury.owner == ctx.program_id {
+            Account::<Treasury>::try_from(&treasury)?
+                .close(ctx.accounts.signer.to_account_info())?;
+        }
+
+        ctx.accounts.state.active_stores = ctx.accounts.state.active_stores.saturating_sub(1);
 
         Ok(())
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn create_coupon(
         ctx: Context<CreateCoupon>,
         name: String,
@@ -88,51 +362,684 @@ pub mod christmas {
         uri: String,
         valid_from: u64,
         valid_to: u64,
+        options: CouponOptions,
+        terms: CouponTerms,
     ) -> Result<()> {
-        // check valid region
-        code_bytes_to_country(&region).unwrap();
+        // check program is not paused
+        require!(!ctx.accounts.state.paused, ChristmasError::ProgramPaused);
+
+        // coupon can only be created once
+        require!(
+            ctx.accounts.coupon.bump == 0,
+            ChristmasError::CouponAlreadyExists
+        );
+
+        // check signer can create coupons for the store
+        require!(
+            is_authorized(
+                &ctx.accounts.store,
+                ctx.accounts.signer.key(),
+                &ctx.accounts.role,
+                PERMISSION_CREATE_COUPONS
+            ),
+            ChristmasError::Unauthorized
+        );
+
+        // check store can have another coupon
+        ctx.accounts
+            .config
+            .validate_live_coupons(ctx.accounts.store.live_coupons)?;
+
+        // check valid region and dates
+        ctx.accounts.config.validate_region(&region)?;
+        validate_date_range(valid_from, valid_to)?;
+        options.schedule.validate()?;
+        terms.validate()?;
 
         ctx.accounts.coupon.bump = *ctx.bumps.get("coupon").unwrap();
-        ctx.accounts.coupon.update_authority = ctx.accounts.signer.key();
+        ctx.accounts.coupon.update_authority = ctx.accounts.store.key(); // follows the store owner
         ctx.accounts.coupon.mint = ctx.accounts.mint.key();
         ctx.accounts.coupon.store = ctx.accounts.store.key();
-        ctx.accounts.coupon.name = pad_string(&name, COUPON_NAME_SIZE - STRING_PREFIX_SIZE);
-        ctx.accounts.coupon.uri = pad_string(&uri, URI_SIZE - STRING_PREFIX_SIZE);
+        ctx.accounts.coupon.name = pad_string(
+            &name,
+            COUPON_NAME_SIZE - STRING_PREFIX_SIZE,
+            ChristmasError::NameTooLong,
+        )?;
+        ctx.accounts.coupon.uri = pad_string(
+            &uri,
+            URI_SIZE - STRING_PREFIX_SIZE,
+            ChristmasError::UriTooLong,
+        )?;
         ctx.accounts.coupon.region = region;
         ctx.accounts.coupon.geohash = geohash;
-        ctx.accounts.coupon.valid_from = valid_from;
-        ctx.accounts.coupon.valid_to = valid_to;
+        ctx.accounts.coupon.set_validity(valid_from, valid_to);
+        ctx.accounts.coupon.options = options;
+        ctx.accounts.coupon.terms = terms;
 
         // init supply
         ctx.accounts.coupon.has_supply = false;
         ctx.accounts.coupon.supply = 0;
 
-        // calculate date hashes
-        let valid_from_days: u64 = epoch_days_from_date(valid_from);
-        let valid_to_days: u64 = epoch_days_from_date(valid_to);
-        ctx.accounts.coupon.datehash_overflow = valid_from_days > valid_to_days;
-        ctx.accounts.coupon.valid_from_hash = days_to_byte_mask(valid_from_days);
-        ctx.accounts.coupon.valid_to_hash = days_to_byte_mask(valid_to_days);
+        // track coupons so the store can't be closed while they are live
+        ctx.accounts.store.live_coupons = ctx.accounts.store.live_coupons.saturating_add(1);
 
         // check existing region market region else create ([0, 0, 0] is uninitialized)
         if ctx.accounts.region_market.region == [0, 0, 0] {
             ctx.accounts.region_market.region = region;
             ctx.accounts.region_market.bump = *ctx.bumps.get("region_market").unwrap();
         } else {
-            assert!(ctx.accounts.region_market.region == region);
-            assert!(ctx.accounts.region_market.bump == *ctx.bumps.get("region_market").unwrap())
+            require!(
+                ctx.accounts.region_market.region == region,
+                ChristmasError::MarketMismatch
+            );
+            require!(
+                ctx.accounts.region_market.bump == *ctx.bumps.get("region_market").unwrap(),
+                ChristmasError::MarketMismatch
+            );
+        }
+        Ok(())
+    }
+
+    pub fn update_coupon(
+        ctx: Context<UpdateCoupon>,
+        name: String,
+        geohash: [u8; 6],
+        uri: String,
+        valid_from: u64,
+        valid_to: u64,
+    ) -> Result<()> {
+        // check valid dates
+        validate_date_range(valid_from, valid_to)?;
+
+        ctx.accounts.coupon.name = pad_string(
+            &name,
+            COUPON_NAME_SIZE - STRING_PREFIX_SIZE,
+            ChristmasError::NameTooLong,
+        )?;
+        ctx.accounts.coupon.uri = pad_string(
+            &uri,
+            URI_SIZE - STRING_PREFIX_SIZE,
+            ChristmasError::UriTooLong,
+        )?;
+        ctx.accounts.coupon.geohash = geohash;
+        ctx.accounts.coupon.set_validity(valid_from, valid_to);
+
+        emit!(CouponUpdated {
+            coupon: ctx.accounts.coupon.key(),
+            name: ctx.accounts.coupon.name.clone(),
+            geohash,
+            uri: ctx.accounts.coupon.uri.clone(),
+            valid_from,
+            valid_to,
+        });
+
+        Ok(())
+    }
+
+    pub fn set_coupon_update_authority(
+        ctx: Context<UpdateCoupon>,
+        update_authority: Pubkey,
+    ) -> Result<()> {
+        let previous_update_authority = ctx.accounts.coupon.update_authority;
+        ctx.accounts.coupon.update_authority = update_authority;
+
+        emit!(CouponUpdateAuthorityChanged {
+            coupon: ctx.accounts.coupon.key(),
+            previous_update_authority,
+            update_authority,
+        });
+
+        Ok(())
+    }
+
+    pub fn migrate_mint_authority(ctx: Context<MigrateMintAuthority>) -> Result<()> {
+        // grow coupons created before options and terms were added so they can be loaded
+        let coupon = ctx.accounts.coupon.to_account_info();
+        if coupon.owner == ctx.program_id && coupon.data_len() == Coupon::legacy_len() {
+            realloc_with_rent(
+                &coupon,
+                Coupon::len(),
+                &ctx.accounts.signer.to_account_info(),
+                &ctx.accounts.system_program.to_account_info(),
+            )?;
+            Coupon::migrate_legacy(&mut coupon.try_borrow_mut_data()?);
         }
+        let mut coupon = Account::<Coupon>::try_from(&coupon)?;
+
+        // legacy coupons have the merchant wallet as update authority, hand it to the store so it
+        // follows ownership transfers
+        if coupon.update_authority == ctx.accounts.signer.key() {
+            coupon.update_authority = coupon.store;
+            coupon.exit(ctx.program_id)?;
+        }
+
+        // hand the mint (and freeze) authority to the coupon so all minting is through the program
+        set_authority(
+            CpiContext::new(
+                ctx.accounts.token_program.to_account_info(),
+                SetAuthority {
+                    current_authority: ctx.accounts.signer.to_account_info(),
+                    account_or_mint: ctx.accounts.mint.to_account_info(),
+                },
+            ),
+            AuthorityType::MintTokens,
+            Some(coupon.key()),
+        )?;
+        if ctx.accounts.mint.freeze_authority == COption::Some(ctx.accounts.signer.key()) {
+            set_authority(
+                CpiContext::new(
+                    ctx.accounts.token_program.to_account_info(),
+                    SetAuthority {
+                        current_authority: ctx.accounts.signer.to_account_info(),
+                        account_or_mint: ctx.accounts.mint.to_account_info(),
+                    },
+                ),
+                AuthorityType::FreezeAccount,
+                Some(coupon.key()),
+            )?;
+        }
+
+        Ok(())
+    }
+
+    pub fn close_coupon(ctx: Context<CloseCoupon>) -> Result<()> {
+        // check program is not paused
+        require!(!ctx.accounts.state.paused, ChristmasError::ProgramPaused);
+
+        // anyone can retire an expired coupon, the update authority can retire it at any time
+        if !ctx
+            .accounts
+            .coupon
+            .is_update_authority(&ctx.accounts.store, ctx.accounts.signer.key())
+        {
+            require!(
+                current_timestamp_ms()? > ctx.accounts.coupon.valid_to,
+                ChristmasError::CouponNotExpired
+            );
+        }
+
+        let market_seeds: &[&[&[u8]]] = &[&[
+            b"market".as_ref(),
+            &ctx.accounts.region_market.region,
+            &[ctx.accounts.region_market.bump],
+        ]];
+
+        // burn remaining market supply
+        let amount = ctx.accounts.region_market_token_account.amount;
+        if amount > 0 {
+            burn(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Burn {
+                        mint: ctx.accounts.mint.to_account_info(),
+                        authority: ctx.accounts.region_market.to_account_info(),
+                        from: ctx.accounts.region_market_token_account.to_account_info(),
+                    },
+                    market_seeds,
+                ),
+                amount,
+            )?;
+        }
+
+        // close market token account (`coupon` is closed by anchor)
+        close_account(CpiContext::new_with_signer(
+            ctx.accounts.token_program.to_account_info(),
+            CloseAccount {
+                account: ctx.accounts.region_market_token_account.to_account_info(),
+                destination: ctx.accounts.store_owner.to_account_info(),
+                authority: ctx.accounts.region_market.to_account_info(),
+            },
+            market_seeds,
+        ))?;
+
+        ctx.accounts.store.live_coupons = ctx.accounts.store.live_coupons.saturating_sub(1);
+
         Ok(())
     }
 
-    pub fn redeem_coupon(ctx: Context<RedeemCoupon>, num_tokens: u64) -> Result<()> {
+    pub fn redeem_coupon(
+        ctx: Context<RedeemCoupon>,
+        num_tokens: u64, // number of uses for multi-use coupons
+        redemption_id: u64,
+        reference: String,
+    ) -> Result<()> {
+        // check program is not paused
+        require!(!ctx.accounts.state.paused, ChristmasError::ProgramPaused);
+
+        // every redemption burns a token or consumes a use (it punches the loyalty card)
+        require!(num_tokens > 0, ChristmasError::InvalidNumTokens);
+
+        // check coupon region is enabled
+        ctx.accounts
+            .config
+            .validate_region(&ctx.accounts.coupon.region)?;
+
+        // check coupon is valid now
+        let timestamp = current_timestamp_ms()?;
+        ctx.accounts.coupon.validate_period(timestamp)?;
+        require!(
+            ctx.accounts.coupon.options.schedule.contains(timestamp),
+            ChristmasError::CouponNotInSchedule
+        );
+
+        // check store (owner or delegate) has agreed to the redemption
+        if ctx.accounts.coupon.options.cosigned_redeem {
+            require!(
+                matches!(&ctx.accounts.store_signer, Some(s) if is_authorized(
+                    &ctx.accounts.store,
+                    s.key(),
+                    &ctx.accounts.store_signer_role,
+                    PERMISSION_VERIFY_REDEMPTIONS
+                )),
+                ChristmasError::StoreSignatureRequired
+            );
+        }
+
+        // multi-use coupons only burn used up tokens
+        let uses_per_token = ctx.accounts.coupon.options.uses_per_token;
+        let num_burn = if uses_per_token > 1 {
+            let uses = match &mut ctx.accounts.uses {
+                Some(uses) => uses,
+                None => return err!(ChristmasError::MissingUsesAccount),
+            };
+
+            // set uses fields if it is being created
+            if uses.bump == 0 {
+                uses.bump = *ctx.bumps.get("uses").unwrap();
+            }
+
+            let num_burn = uses.consume(uses_per_token, num_tokens);
+            let num_started = (uses.remaining > 0) as u64;
+            require!(
+                ctx.accounts.user_token_account.amount >= num_burn + num_started,
+                ChristmasError::InsufficientCouponBalance
+            );
+            num_burn
+        } else {
+            num_tokens
+        };
+
+        let mint_key = ctx.accounts.mint.key();
+        let coupon_seeds: &[&[&[u8]]] = &[&[
+            b"coupon".as_ref(),
+            mint_key.as_ref(),
+            &[ctx.accounts.coupon.bump],
+        ]];
+
+        // thaw `soulbound` coupons only to burn them
+        let soulbound = ctx.accounts.coupon.options.soulbound && num_burn > 0;
+        if soulbound && ctx.accounts.user_token_account.is_frozen() {
+            thaw_account(CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                ThawAccount {
+                    account: ctx.accounts.user_token_account.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    authority: ctx.accounts.coupon.to_account_info(),
+                },
+                coupon_seeds,
+            ))?;
+        }
+
+        if num_burn > 0 {
+            burn(
+                CpiContext::new_with_signer(
+                    ctx.accounts.token_program.to_account_info(),
+                    Burn {
+                        mint: ctx.accounts.mint.to_account_info(),
+                        authority: ctx.accounts.user.to_account_info(),
+                        from: ctx.accounts.user_token_account.to_account_info(),
+                    },
+                    &[&[
+                        b"user".as_ref(),
+                        ctx.accounts.signer.key().as_ref(),
+                        &[ctx.accounts.user.bump],
+                    ]],
+                ),
+                num_burn,
+            )?;
+        }
+
+        if soulbound {
+            freeze_account(CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                FreezeAccount {
+                    account: ctx.accounts.user_token_account.to_account_info(),
+                    mint: ctx.accounts.mint.to_account_info(),
+                    authority: ctx.accounts.coupon.to_account_info(),
+                },
+                coupon_seeds,
+            ))?;
+        }
+
+        // record receipt
+        ctx.accounts.redemption.id = redemption_id;
+        ctx.accounts.redemption.coupon = ctx.accounts.coupon.key();
+        ctx.accounts.redemption.store = ctx.accounts.store.key();
+        ctx.accounts.redemption.user = ctx.accounts.signer.key();
+        ctx.accounts.redemption.num_tokens = num_tokens;
+        ctx.accounts.redemption.timestamp = timestamp;
+        ctx.accounts.redemption.reference = pad_string(
+            &reference,
+            REFERENCE_SIZE - STRING_PREFIX_SIZE,
+            ChristmasError::ReferenceTooLong,
+        )?;
+        ctx.accounts.redemption.payer = ctx.accounts.payer.key();
+        ctx.accounts.redemption.bump = *ctx.bumps.get("redemption").unwrap();
+
+        // earn store points
+        if let Some(store_points) = &ctx.accounts.store_points {
+            mint_points(
+                store_points,
+                &ctx.accounts.points_mint,
+                &ctx.accounts.user_points_token_account,
+                &ctx.accounts.user,
+                &ctx.accounts.token_program,
+                store_points.redeem_rate,
+                num_tokens,
+            )?;
+        }
+
+        // punch the user's loyalty card (if the store has a loyalty program)
+        let (loyalty_program, punch_card) =
+            match (&ctx.accounts.loyalty_program, &mut ctx.accounts.punch_card) {
+                (Some(loyalty_program), Some(punch_card)) => (loyalty_program, punch_card),
+                (Some(_), None) => return err!(ChristmasError::InvalidLoyaltyAccounts),
+                (None, _) => return Ok(()),
+            };
+        if punch_card.bump == 0 {
+            punch_card.bump = *ctx.bumps.get("punch_card").unwrap();
+        }
+        punch_card.punches = punch_card.punches.saturating_add(1);
+        if punch_card.punches < loyalty_program.threshold {
+            return Ok(());
+        }
+        punch_card.punches = 0;
+
+        // mint the reward coupon to the user (the reward coupon must be its mint authority)
+        let user_key = ctx.accounts.user.key();
+        let (reward_coupon, reward_mint, to, associated_token_program) = match (
+            &mut ctx.accounts.reward_coupon,
+            &ctx.accounts.reward_mint,
+            &ctx.accounts.reward_user_token_account,
+            &ctx.accounts.associated_token_program,
+        ) {
+            (Some(coupon), Some(mint), Some(to), Some(program))
+                if coupon.key() == loyalty_program.reward_coupon
+                    && coupon.mint == mint.key()
+                    && to.key() == get_associated_token_address(&user_key, &mint.key()) =>
+            {
+                (coupon, mint, to, program)
+            }
+            _ => return err!(ChristmasError::InvalidLoyaltyAccounts),
+        };
+
+        // check supply before minting
+        let supply = checked_add_supply(reward_coupon.supply, 1)?;
+        let max_supply = reward_coupon.options.max_supply;
+        require!(
+            max_supply == 0 || supply <= max_supply,
+            ChristmasError::MaxSupplyExceeded
+        );
+        reward_coupon.supply = supply;
+
+        create_idempotent(CpiContext::new(
+            associated_token_program.to_account_info(),
+            Create {
+                payer: ctx.accounts.payer.to_account_info(),
+                associated_token: to.to_account_info(),
+                authority: ctx.accounts.user.to_account_info(),
+                mint: reward_mint.to_account_info(),
+                system_program: ctx.accounts.system_program.to_account_info(),
+                token_program: ctx.accounts.token_program.to_account_info(),
+            },
+        ))?;
+
+        send_coupon(
+            reward_coupon,
+            reward_mint,
+            &to.to_account_info(),
+            None,
+            &ctx.accounts.token_program,
+            1,
+        )
+    }
+
+    pub fn close_redemptions<'info>(
+        ctx: Context<'_, '_, '_, 'info, CloseRedemptions<'info>>,
+    ) -> Result<()> {
+        // receipts are passed as (redemption, payer) pairs
+        let pairs = ctx.remaining_accounts.chunks_exact(2);
+        require!(
+            pairs.remainder().is_empty(),
+            ChristmasError::RedemptionPayerMismatch
+        );
+
+        // check all receipts belong to the store before closing any
+        let mut redemptions = vec![];
+        for accounts in pairs {
+            let redemption = Account::<Redemption>::try_from(&accounts[0])?;
+            require_keys_eq!(
+                redemption.store,
+                ctx.accounts.store.key(),
+                ChristmasError::RedemptionStoreMismatch
+            );
+            require_keys_eq!(
+                redemption.payer,
+                accounts[1].key(),
+                ChristmasError::RedemptionPayerMismatch
+            );
+            redemptions.push((redemption, accounts[1].clone()));
+        }
+
+        // return rent to whoever paid for the receipt
+        for (redemption, payer) in redemptions {
+            redemption.close(payer)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn set_loyalty_program(ctx: Context<SetLoyaltyProgram>, threshold: u32) -> Result<()> {
+        require!(threshold > 0, ChristmasError::InvalidLoyaltyThreshold);
+
+        ctx.accounts.loyalty_program.store = ctx.accounts.store.key();
+        ctx.accounts.loyalty_program.reward_coupon = ctx.accounts.reward_coupon.key();
+        ctx.accounts.loyalty_program.reward_mint = ctx.accounts.reward_coupon.mint;
+        ctx.accounts.loyalty_program.threshold = threshold;
+        ctx.accounts.loyalty_program.bump = *ctx.bumps.get("loyalty_program").unwrap();
+
+        Ok(())
+    }
+
+    pub fn set_store_points(
+        ctx: Context<SetStorePoints>,
+        redeem_rate: u64,
+        claim_rate: u64,
+        reward_cost: u64,
+    ) -> Result<()> {
+        // set store points fields if it is being created
+        if ctx.accounts.store_points.bump == 0 {
+            ctx.accounts.store_points.store = ctx.accounts.store.key();
+            ctx.accounts.store_points.mint = ctx.accounts.points_mint.key();
+            ctx.accounts.store_points.bump = *ctx.bumps.get("store_points").unwrap();
+        }
+
+        ctx.accounts.store_points.redeem_rate = redeem_rate;
+        ctx.accounts.store_points.claim_rate = claim_rate;
+        ctx.accounts.store_points.reward_coupon = match &ctx.accounts.reward_coupon {
+            Some(reward_coupon) => reward_coupon.key(),
+            None => Pubkey::default(),
+        };
+        ctx.accounts.store_points.reward_cost = reward_cost;
+
+        Ok(())
+    }
+
+    pub fn spend_points(ctx: Context<SpendPoints>, num_tokens: u64) -> Result<()> {
+        // check program is not paused
+        require!(!ctx.accounts.state.paused, ChristmasError::ProgramPaused);
+
+        let reward_cost = ctx.accounts.store_points.reward_cost;
+        require!(reward_cost > 0, ChristmasError::PointsRewardNotSet);
+
+        // check reward coupon region is enabled and the coupon is valid now (as when claiming)
+        ctx.accounts
+            .config
+            .validate_region(&ctx.accounts.reward_coupon.region)?;
+        ctx.accounts
+            .reward_coupon
+            .validate_period(current_timestamp_ms()?)?;
+
+        // check claim limit per user (set claim record fields if it is being created)
+        if ctx.accounts.claim_record.bump == 0 {
+            ctx.accounts.claim_record.bump = *ctx.bumps.get("claim_record").unwrap();
+        }
+        ctx.accounts.claim_record.add_claimed(
+            num_tokens,
+            ctx.accounts.reward_coupon.options.max_claims_per_user,
+        )?;
+
+        // check the market has enough tokens
+        let market_balance = ctx
+            .accounts
+            .region_market_token_account
+            .amount
+            .checked_sub(num_tokens)
+            .ok_or(ChristmasError::InsufficientSupply)?;
+
         burn(
             CpiContext::new_with_signer(
                 ctx.accounts.token_program.to_account_info(),
                 Burn {
-                    mint: ctx.accounts.mint.to_account_info(),
+                    mint: ctx.accounts.points_mint.to_account_info(),
                     authority: ctx.accounts.user.to_account_info(),
+                    from: ctx.accounts.user_points_token_account.to_account_info(),
+                },
+                &[&[
+                    b"user".as_ref(),
+                    ctx.accounts.signer.key().as_ref(),
+                    &[ctx.accounts.user.bump],
+                ]],
+            ),
+            reward_cost
+                .checked_mul(num_tokens)
+                .ok_or(ChristmasError::PointsOverflow)?,
+        )?;
+
+        send_coupon(
+            &ctx.accounts.reward_coupon,
+            &ctx.accounts.reward_mint,
+            &ctx.accounts.user_token_account.to_account_info(),
+            Some((
+                &ctx.accounts.region_market,
+                &ctx.accounts.region_market_token_account,
+            )),
+            &ctx.accounts.token_program,
+            num_tokens,
+        )?;
+
+        // update supply (the last claim empties the market)
+        ctx.accounts.reward_coupon.has_supply = market_balance > 0;
+
+        Ok(())
+    }
+
+    pub fn grant_role(ctx: Context<GrantRole>, permissions: u8) -> Result<()> {
+        require!(
+            is_authorized(
+                &ctx.accounts.store,
+                ctx.accounts.signer.key(),
+                &ctx.accounts.signer_role,
+                PERMISSION_MANAGE_STAFF
+            ),
+            ChristmasError::Unauthorized
+        );
+        require!(
+            permissions != 0 && permissions & !PERMISSION_ALL == 0,
+            ChristmasError::InvalidPermissions
+        );
+
+        // delegates can only grant the permissions they hold
+        if ctx.accounts.signer.key() != ctx.accounts.store.owner {
+            let signer_permissions = ctx
+                .accounts
+                .signer_role
+                .as_ref()
+                .map_or(0, |role| role.permissions);
+            require!(
+                permissions & !signer_permissions == 0,
+                ChristmasError::Unauthorized
+            );
+        }
+
+        ctx.accounts.role.store = ctx.accounts.store.key();
+        ctx.accounts.role.delegate = ctx.accounts.delegate.key();
+        ctx.accounts.role.permissions = permissions;
+        ctx.accounts.role.epoch = ctx.accounts.store.role_epoch;
+        ctx.accounts.role.bump = *ctx.bumps.get("role").unwrap();
+
+        Ok(())
+    }
+
+    pub fn revoke_role(ctx: Context<RevokeRole>) -> Result<()> {
+        require!(
+            is_authorized(
+                &ctx.accounts.store,
+                ctx.accounts.signer.key(),
+                &ctx.accounts.signer_role,
+                PERMISSION_MANAGE_STAFF
+            ),
+            ChristmasError::Unauthorized
+        );
+
+        Ok(())
+    }
+
+    pub fn transfer_coupon(ctx: Context<TransferCoupon>, num_tokens: u64) -> Result<()> {
+        // check program is not paused
+        require!(!ctx.accounts.state.paused, ChristmasError::ProgramPaused);
+
+        // check merchant allows gifting
+        require!(
+            ctx.accounts.coupon.options.transferable && !ctx.accounts.coupon.options.soulbound,
+            ChristmasError::CouponNotTransferable
+        );
+
+        // a partly used token can't be given away (the recipient would get all its uses back)
+        if ctx.accounts.coupon.options.uses_per_token > 1 {
+            let uses = match &ctx.accounts.uses {
+                Some(uses) => uses.to_account_info(),
+                None => return err!(ChristmasError::MissingUsesAccount),
+            };
+            let remaining = if uses.owner == ctx.program_id {
+                Account::<CouponUses>::try_from(&uses)?.remaining
+            } else {
+                0
+            };
+            let num_started = (remaining > 0) as u64;
+            require!(
+                ctx.accounts
+                    .user_token_account
+                    .amount
+                    .saturating_sub(num_started)
+                    >= num_tokens,
+                ChristmasError::CouponPartlyUsed
+            );
+        }
+
+        // set recipient user fields (from coupon) if it is being created
+        if ctx.accounts.recipient_user.bump == 0 {
+            ctx.accounts.recipient_user.region = ctx.accounts.coupon.region;
+            ctx.accounts.recipient_user.bump = *ctx.bumps.get("recipient_user").unwrap();
+        }
+
+        transfer(
+            CpiContext::new_with_signer(
+                ctx.accounts.token_program.to_account_info(),
+                Transfer {
                     from: ctx.accounts.user_token_account.to_account_info(),
+                    to: ctx.accounts.recipient_token_account.to_account_info(),
+                    authority: ctx.accounts.user.to_account_info(),
                 },
                 &[&[
                     b"user".as_ref(),
@@ -151,51 +1058,83 @@ pub mod christmas {
         region: [u8; 3],
         num_tokens: u64,
     ) -> Result<()> {
-        // check valid region
-        code_bytes_to_country(&region).unwrap();
+        // check program is not paused
+        require!(!ctx.accounts.state.paused, ChristmasError::ProgramPaused);
 
+        // check valid and enabled region, and the mint limit
+        ctx.accounts.config.validate_region(&region)?;
+        ctx.accounts.config.validate_mint(num_tokens)?;
+
+        // check signer can mint for the store
+        require!(
+            is_authorized(
+                &ctx.accounts.store,
+                ctx.accounts.signer.key(),
+                &ctx.accounts.role,
+                PERMISSION_MINT_TO_MARKET
+            ),
+            ChristmasError::Unauthorized
+        );
+
+        let mint_key = ctx.accounts.mint.key();
         let cpi_accounts = MintTo {
             mint: ctx.accounts.mint.to_account_info(),
             to: ctx.accounts.region_market_token_account.to_account_info(),
-            authority: ctx.accounts.signer.to_account_info(),
+            authority: ctx.accounts.coupon.to_account_info(),
         };
         let cpi_program = ctx.accounts.token_program.to_account_info();
-        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        let coupon_seeds: &[&[&[u8]]] = &[&[
+            b"coupon".as_ref(),
+            mint_key.as_ref(),
+            &[ctx.accounts.coupon.bump],
+        ]];
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, coupon_seeds);
 
         ctx.accounts.region_market.bump = *ctx.bumps.get("region_market").unwrap();
         ctx.accounts.region_market.region = region;
 
+        // check supply before minting (dont allow more than u32 tokens)
+        let supply = checked_add_supply(ctx.accounts.coupon.supply, num_tokens)?;
+        let max_supply = ctx.accounts.coupon.options.max_supply;
+        require!(
+            max_supply == 0 || supply <= max_supply,
+            ChristmasError::MaxSupplyExceeded
+        );
+
         mint_to(cpi_ctx, num_tokens)?;
 
-        // update supply (NOTE: will panic when overflow but its ok - dont allow more than u32 tokens (throw a better error))
-        ctx.accounts.coupon.supply += num_tokens as u32;
-        if ctx.accounts.coupon.supply > 0 {
-            ctx.accounts.coupon.has_supply = true;
-        } else {
-            ctx.accounts.coupon.has_supply = false;
-        }
+        // update supply (market may be empty after claims even if the supply is not)
+        let market_balance = ctx
+            .accounts
+            .region_market_token_account
+            .amount
+            .saturating_add(num_tokens);
+        ctx.accounts.coupon.supply = supply;
+        ctx.accounts.coupon.has_supply = market_balance > 0;
 
         Ok(())
     }
 
-    pub fn claim_from_market(ctx: Context<ClaimFromMarket>, num_tokens: u64) -> Result<()> {
-        // set user fields (from region_market) if it is being created
-        if ctx.accounts.user.bump == 0 {
-            ctx.accounts.user.region = ctx.accounts.region_market.region;
-            ctx.accounts.user.bump = *ctx.bumps.get("user").unwrap();
-        }
+    pub fn burn_from_market(ctx: Context<BurnFromMarket>, num_tokens: u64) -> Result<()> {
+        // check program is not paused
+        require!(!ctx.accounts.state.paused, ChristmasError::ProgramPaused);
 
-        transfer(
+        // check supply before burning (claimed tokens can't be burnt from the market)
+        let supply = checked_sub_supply(ctx.accounts.coupon.supply, num_tokens)?;
+        let market_balance = ctx
+            .accounts
+            .region_market_token_account
+            .amount
+            .checked_sub(num_tokens)
+            .ok_or(ChristmasError::InsufficientSupply)?;
+
+        burn(
             CpiContext::new_with_signer(
                 ctx.accounts.token_program.to_account_info(),
-                Transfer {
-                    from: ctx
-                        .accounts
-                        .region_market_token_account
-                        .to_account_info()
-                        .clone(),
-                    to: ctx.accounts.user_token_account.to_account_info().clone(),
-                    authority: ctx.accounts.region_market.to_account_info().clone(),
+                Burn {
+                    mint: ctx.accounts.mint.to_account_info(),
+                    authority: ctx.accounts.region_market.to_account_info(),
+                    from: ctx.accounts.region_market_token_account.to_account_info(),
                 },
                 &[&[
                     b"market".as_ref(),
@@ -206,6 +1145,202 @@ pub mod christmas {
             num_tokens,
         )?;
 
+        // update supply
+        ctx.accounts.coupon.supply = supply;
+        ctx.accounts.coupon.has_supply = market_balance > 0;
+
+        Ok(())
+    }
+
+    pub fn claim_from_market(ctx: Context<ClaimFromMarket>, num_tokens: u64) -> Result<()> {
+        // check program is not paused
+        require!(!ctx.accounts.state.paused, ChristmasError::ProgramPaused);
+
+        // check coupon region is enabled and the coupon is valid now
+        ctx.accounts
+            .config
+            .validate_region(&ctx.accounts.coupon.region)?;
+        ctx.accounts
+            .coupon
+            .validate_period(current_timestamp_ms()?)?;
+
+        // check the market has enough tokens
+        let market_balance = ctx
+            .accounts
+            .region_market_token_account
+            .amount
+            .checked_sub(num_tokens)
+            .ok_or(ChristmasError::InsufficientSupply)?;
+
+        // set user fields (from region_market) if it is being created
+        if ctx.accounts.user.bump == 0 {
+            ctx.accounts.user.region = ctx.accounts.region_market.region;
+            ctx.accounts.user.bump = *ctx.bumps.get("user").unwrap();
+        }
+
+        // check claim limit per user (set claim record fields if it is being created)
+        if ctx.accounts.claim_record.bump == 0 {
+            ctx.accounts.claim_record.bump = *ctx.bumps.get("claim_record").unwrap();
+        }
+        ctx.accounts
+            .claim_record
+            .add_claimed(num_tokens, ctx.accounts.coupon.options.max_claims_per_user)?;
+
+        // pay the store treasury (less the protocol fee) for paid coupons
+        let price = ctx.accounts.coupon.options.price;
+        if price > 0 {
+            let treasury = match &mut ctx.accounts.treasury {
+                Some(treasury) => treasury,
+                None => return err!(ChristmasError::InvalidPaymentAccounts),
+            };
+
+            // set treasury fields if it is being created
+            if treasury.bump == 0 {
+                treasury.store = ctx.accounts.coupon.store;
+                treasury.bump = *ctx.bumps.get("treasury").unwrap();
+            }
+            let treasury = treasury.to_account_info();
+
+            let amount = price
+                .checked_mul(num_tokens)
+                .ok_or(ChristmasError::PriceOverflow)?;
+            let fee = ctx.accounts.config.fee(amount);
+            let price_mint = ctx.accounts.coupon.options.price_mint;
+            if price_mint == Pubkey::default() {
+                let mut payments = vec![(treasury, amount - fee)];
+                if fee > 0 {
+                    match &ctx.accounts.fee_recipient {
+                        Some(fee_recipient) => {
+                            payments.push((fee_recipient.to_account_info(), fee))
+                        }
+                        None => return err!(ChristmasError::InvalidPaymentAccounts),
+                    }
+                }
+                for (to, lamports) in payments {
+                    system_program::transfer(
+                        CpiContext::new(
+                            ctx.accounts.system_program.to_account_info(),
+                            system_program::Transfer {
+                                from: ctx.accounts.signer.to_account_info(),
+                                to,
+                            },
+                        ),
+                        lamports,
+                    )?;
+                }
+            } else {
+                let from = match &ctx.accounts.signer_token_account {
+                    Some(from) if from.mint == price_mint => from,
+                    _ => return err!(ChristmasError::InvalidPaymentAccounts),
+                };
+                let mut payments = match &ctx.accounts.treasury_token_account {
+                    Some(to) if to.mint == price_mint && to.owner == treasury.key() => {
+                        vec![(to, amount - fee)]
+                    }
+                    _ => return err!(ChristmasError::InvalidPaymentAccounts),
+                };
+                if fee > 0 {
+                    match &ctx.accounts.fee_token_account {
+                        Some(to)
+                            if to.mint == price_mint
+                                && to.owner == ctx.accounts.config.fee_recipient =>
+                        {
+                            payments.push((to, fee))
+                        }
+                        _ => return err!(ChristmasError::InvalidPaymentAccounts),
+                    }
+                }
+                for (to, tokens) in payments {
+                    transfer(
+                        CpiContext::new(
+                            ctx.accounts.token_program.to_account_info(),
+                            Transfer {
+                                from: from.to_account_info(),
+                                to: to.to_account_info(),
+                                authority: ctx.accounts.signer.to_account_info(),
+                            },
+                        ),
+                        tokens,
+                    )?;
+                }
+            }
+
+            // earn store points
+            if let Some(store_points) = &ctx.accounts.store_points {
+                mint_points(
+                    store_points,
+                    &ctx.accounts.points_mint,
+                    &ctx.accounts.user_points_token_account,
+                    &ctx.accounts.user,
+                    &ctx.accounts.token_program,
+                    store_points.claim_rate,
+                    num_tokens,
+                )?;
+            }
+        }
+
+        send_coupon(
+            &ctx.accounts.coupon,
+            &ctx.accounts.mint,
+            &ctx.accounts.user_token_account.to_account_info(),
+            Some((
+                &ctx.accounts.region_market,
+                &ctx.accounts.region_market_token_account,
+            )),
+            &ctx.accounts.token_program,
+            num_tokens,
+        )?;
+
+        // update supply (the last claim empties the market)
+        ctx.accounts.coupon.has_supply = market_balance > 0;
+
+        Ok(())
+    }
+
+    pub fn withdraw_treasury(ctx: Context<WithdrawTreasury>, amount: u64) -> Result<()> {
+        // check program is not paused
+        require!(!ctx.accounts.state.paused, ChristmasError::ProgramPaused);
+
+        match (
+            &ctx.accounts.treasury_token_account,
+            &ctx.accounts.destination_token_account,
+        ) {
+            // SPL tokens
+            (Some(from), Some(to)) => {
+                transfer(
+                    CpiContext::new_with_signer(
+                        ctx.accounts.token_program.to_account_info(),
+                        Transfer {
+                            from: from.to_account_info(),
+                            to: to.to_account_info(),
+                            authority: ctx.accounts.treasury.to_account_info(),
+                        },
+                        &[&[
+                            b"treasury".as_ref(),
+                            ctx.accounts.store.key().as_ref(),
+                            &[ctx.accounts.treasury.bump],
+                        ]],
+                    ),
+                    amount,
+                )?;
+            }
+            // lamports (treasury must stay rent exempt)
+            (None, None) => {
+                let treasury = ctx.accounts.treasury.to_account_info();
+                let min_balance = Rent::get()?.minimum_balance(treasury.data_len());
+                require!(
+                    treasury.lamports().saturating_sub(min_balance) >= amount,
+                    ChristmasError::InsufficientTreasuryBalance
+                );
+                **treasury.try_borrow_mut_lamports()? -= amount;
+                **ctx.accounts.signer.try_borrow_mut_lamports()? += amount;
+            }
+            _ => return err!(ChristmasError::InvalidPaymentAccounts),
+        }
+
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests;
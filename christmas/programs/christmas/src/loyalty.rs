@@ -0,0 +1,61 @@
+use anchor_lang::prelude::*;
+
+use crate::coupon::Coupon;
+use crate::defs::*;
+use crate::store::Store;
+
+#[derive(Accounts)]
+pub struct SetLoyaltyProgram<'info> {
+    #[account(
+        constraint = store.owner == signer.key(), // only the owner can set the loyalty program
+    )]
+    pub store: Account<'info, Store>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        seeds = [b"loyalty", store.key().as_ref()],
+        bump,
+        space = LoyaltyProgram::len(),
+    )]
+    pub loyalty_program: Account<'info, LoyaltyProgram>,
+    #[account(
+        constraint = reward_coupon.store == store.key(), // reward is from the same store
+    )]
+    pub reward_coupon: Account<'info, Coupon>,
+    pub signer: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[account]
+pub struct LoyaltyProgram {
+    pub store: Pubkey,
+    pub reward_coupon: Pubkey,
+    pub reward_mint: Pubkey,
+    pub threshold: u32, // redemptions (of any store coupon) for 1 reward coupon
+    pub bump: u8,
+}
+
+impl LoyaltyProgram {
+    pub fn len() -> usize {
+        DISCRIMINATOR_SIZE
+            + PUBKEY_SIZE // store
+            + PUBKEY_SIZE // reward_coupon
+            + PUBKEY_SIZE // reward_mint
+            + U32_SIZE // threshold
+            + BUMP_SIZE
+    }
+}
+
+#[account]
+pub struct PunchCard {
+    pub punches: u32, // redemptions since the last reward
+    pub bump: u8,
+}
+
+impl PunchCard {
+    pub fn len() -> usize {
+        DISCRIMINATOR_SIZE + U32_SIZE + BUMP_SIZE
+    }
+}
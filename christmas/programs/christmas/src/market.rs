@@ -1,5 +1,12 @@
+use crate::config::Config;
 use crate::coupon::Coupon;
 use crate::defs::*;
+use crate::errors::ChristmasError;
+use crate::points::StorePoints;
+use crate::role::StoreRole;
+use crate::state::ProgramState;
+use crate::store::Store;
+use crate::treasury::Treasury;
 use crate::user::User;
 use anchor_lang::prelude::*;
 use anchor_spl::associated_token::AssociatedToken;
@@ -38,14 +45,65 @@ pub struct ClaimFromMarket<'info> {
     #[account(mut)]
     pub mint: Account<'info, Mint>,
     #[account(
+        mut,
         seeds = [b"coupon", mint.key().as_ref()],
         bump = coupon.bump,
         constraint = coupon.mint == mint.key(), // coupon is for this mint
         constraint = coupon.region == region_market.region  // coupon is for this region
     )]
     pub coupon: Account<'info, Coupon>,
+    // tracks tokens claimed by the user (for `max_claims_per_user`)
+    #[account(
+        init_if_needed,
+        payer = payer,
+        seeds = [b"claim", coupon.key().as_ref(), signer.key().as_ref()],
+        bump,
+        space = ClaimRecord::len(),
+    )]
+    pub claim_record: Account<'info, ClaimRecord>,
+    // required for paid coupons (receives the payment)
+    #[account(
+        init_if_needed,
+        payer = payer,
+        seeds = [b"treasury", coupon.store.as_ref()],
+        bump,
+        space = Treasury::len(),
+    )]
+    pub treasury: Option<Account<'info, Treasury>>,
+    // required if the coupon is priced in an SPL token (`price_mint`)
+    #[account(mut, token::authority = signer)]
+    pub signer_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut)] // owned by `treasury` (checked in the instruction)
+    pub treasury_token_account: Option<Account<'info, TokenAccount>>,
+    // required if the store has points (`store_points`)
+    #[account(
+        seeds = [b"points", coupon.store.as_ref()],
+        bump = store_points.bump,
+    )]
+    pub store_points: Option<Account<'info, StorePoints>>,
+    #[account(mut)]
+    pub points_mint: Option<Account<'info, Mint>>,
+    #[account(mut)]
+    pub user_points_token_account: Option<Account<'info, TokenAccount>>,
     #[account(mut)]
     pub payer: Signer<'info>,
+    #[account(
+        seeds = [b"state"],
+        bump = state.bump,
+    )]
+    pub state: Account<'info, ProgramState>, // checked for `paused`
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>, // checked for disabled regions and the protocol fee
+    // required if the protocol fee is charged (`fee_recipient` for lamports, else its token account)
+    /// CHECK: address is checked against `config.fee_recipient`
+    #[account(mut, address = config.fee_recipient)]
+    pub fee_recipient: Option<UncheckedAccount<'info>>,
+    #[account(mut)]
+    pub fee_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut)] // pays the coupon price
     pub signer: Signer<'info>,
     pub associated_token_program: Program<'info, AssociatedToken>,
     pub token_program: Program<'info, Token>,
@@ -72,8 +130,7 @@ pub struct MintToMarket<'info> {
     pub region_market_token_account: Account<'info, TokenAccount>,
     #[account(
         mut,
-        mint::authority = signer,
-        mint::freeze_authority = signer,
+        mint::authority = coupon, // not the freeze authority (older coupons keep the merchant's)
     )]
     pub mint: Account<'info, Mint>,
     #[account(
@@ -82,9 +139,23 @@ pub struct MintToMarket<'info> {
         bump = coupon.bump,
         constraint = coupon.mint == mint.key(), // coupon is for this mint
         constraint = coupon.region == region_market.region,  // coupon is for this region
-        constraint = coupon.update_authority == signer.key(),
     )]
     pub coupon: Account<'info, Coupon>,
+    #[account(
+        address = coupon.store,
+    )]
+    pub store: Account<'info, Store>,
+    pub role: Option<Account<'info, StoreRole>>, // required if the signer is not the owner
+    #[account(
+        seeds = [b"state"],
+        bump = state.bump,
+    )]
+    pub state: Account<'info, ProgramState>, // checked for `paused`
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>, // checked for region and mint limits
     pub signer: Signer<'info>,
     #[account(mut)]
     pub payer: Signer<'info>,
@@ -93,6 +164,67 @@ pub struct MintToMarket<'info> {
     pub token_program: Program<'info, Token>,
 }
 
+#[derive(Accounts)]
+pub struct BurnFromMarket<'info> {
+    #[account(
+        mut,
+        token::mint = mint,
+        token::authority = region_market
+    )]
+    pub region_market_token_account: Account<'info, TokenAccount>,
+    #[account(
+        seeds = [b"market", region_market.region.as_ref()],
+        bump = region_market.bump,
+        constraint = region_market.region == coupon.region  // coupon is for this region
+    )]
+    pub region_market: Account<'info, RegionMarket>,
+    #[account(mut)] // supply will change
+    pub mint: Account<'info, Mint>,
+    #[account(
+        mut,
+        seeds = [b"coupon", mint.key().as_ref()],
+        bump = coupon.bump,
+        constraint = coupon.mint == mint.key(), // coupon is for this mint
+        constraint = coupon.is_update_authority(&store, signer.key()),
+    )]
+    pub coupon: Account<'info, Coupon>,
+    #[account(address = coupon.store)]
+    pub store: Account<'info, Store>,
+    #[account(
+        seeds = [b"state"],
+        bump = state.bump,
+    )]
+    pub state: Account<'info, ProgramState>, // checked for `paused`
+    pub signer: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+#[account]
+pub struct ClaimRecord {
+    pub claimed: u64,
+    pub bump: u8,
+}
+
+impl ClaimRecord {
+    pub fn len() -> usize {
+        DISCRIMINATOR_SIZE + U64_SIZE + BUMP_SIZE
+    }
+
+    // Adds `num_tokens` to the tokens claimed, up to `max_claims` (0 for unlimited)
+    pub fn add_claimed(&mut self, num_tokens: u64, max_claims: u64) -> Result<()> {
+        let claimed = self
+            .claimed
+            .checked_add(num_tokens)
+            .ok_or(ChristmasError::ClaimLimitExceeded)?;
+        require!(
+            max_claims == 0 || claimed <= max_claims,
+            ChristmasError::ClaimLimitExceeded
+        );
+        self.claimed = claimed;
+        Ok(())
+    }
+}
+
 #[account]
 pub struct RegionMarket {
     pub region: [u8; 3],
@@ -0,0 +1,267 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+use anchor_spl::token::{
+    freeze_account, mint_to, thaw_account, transfer, FreezeAccount, Mint, MintTo, ThawAccount,
+    Token, TokenAccount, Transfer,
+};
+
+use crate::config::Config;
+use crate::coupon::Coupon;
+use crate::defs::*;
+use crate::errors::ChristmasError;
+use crate::market::{ClaimRecord, RegionMarket};
+use crate::state::ProgramState;
+use crate::store::Store;
+use crate::user::User;
+
+#[derive(Accounts)]
+pub struct SetStorePoints<'info> {
+    #[account(
+        constraint = store.owner == signer.key(), // only the owner can set the points
+    )]
+    pub store: Account<'info, Store>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        seeds = [b"points", store.key().as_ref()],
+        bump,
+        space = StorePoints::len(),
+    )]
+    pub store_points: Account<'info, StorePoints>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        seeds = [b"points_mint", store.key().as_ref()],
+        bump,
+        mint::decimals = 0,
+        mint::authority = store_points, // only the program can mint points
+    )]
+    pub points_mint: Account<'info, Mint>,
+    #[account(
+        constraint = reward_coupon.store == store.key(), // reward is from the same store
+    )]
+    pub reward_coupon: Option<Account<'info, Coupon>>,
+    pub signer: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct SpendPoints<'info> {
+    #[account(
+        seeds = [b"points", store_points.store.as_ref()],
+        bump = store_points.bump,
+    )]
+    pub store_points: Account<'info, StorePoints>,
+    #[account(
+        mut, // supply will change
+        address = store_points.mint,
+    )]
+    pub points_mint: Account<'info, Mint>,
+    #[account(
+        seeds = [b"user", signer.key().as_ref()], // signer must be the user
+        bump = user.bump,
+    )]
+    pub user: Account<'info, User>,
+    #[account(
+        mut,
+        associated_token::mint = points_mint,
+        associated_token::authority = user,
+    )]
+    pub user_points_token_account: Account<'info, TokenAccount>,
+    #[account(
+        mut, // `has_supply` will change
+        address = store_points.reward_coupon,
+        constraint = reward_coupon.mint == reward_mint.key(),
+    )]
+    pub reward_coupon: Account<'info, Coupon>,
+    pub reward_mint: Account<'info, Mint>,
+    #[account(
+        seeds = [b"market", region_market.region.as_ref()],
+        bump = region_market.bump,
+        constraint = region_market.region == reward_coupon.region, // coupon is for this region
+    )]
+    pub region_market: Account<'info, RegionMarket>,
+    #[account(
+        mut,
+        token::mint = reward_mint,
+        token::authority = region_market,
+    )]
+    pub region_market_token_account: Account<'info, TokenAccount>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = reward_mint,
+        associated_token::authority = user,
+    )]
+    pub user_token_account: Account<'info, TokenAccount>,
+    // reward coupons count towards `max_claims_per_user`
+    #[account(
+        init_if_needed,
+        payer = payer,
+        seeds = [b"claim", reward_coupon.key().as_ref(), signer.key().as_ref()],
+        bump,
+        space = ClaimRecord::len(),
+    )]
+    pub claim_record: Account<'info, ClaimRecord>,
+    #[account(
+        seeds = [b"state"],
+        bump = state.bump,
+    )]
+    pub state: Account<'info, ProgramState>, // checked for `paused`
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>, // checked for disabled regions
+    pub signer: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Program<'info, Token>,
+    pub system_program: Program<'info, System>,
+}
+
+#[account]
+pub struct StorePoints {
+    pub store: Pubkey,
+    pub mint: Pubkey,
+    pub redeem_rate: u64,      // points per coupon redeemed
+    pub claim_rate: u64,       // points per paid coupon claimed
+    pub reward_coupon: Pubkey, // Pubkey::default() for no reward
+    pub reward_cost: u64,      // points per reward coupon, 0 for no reward
+    pub bump: u8,
+}
+
+impl StorePoints {
+    pub fn len() -> usize {
+        DISCRIMINATOR_SIZE
+            + PUBKEY_SIZE // store
+            + PUBKEY_SIZE // mint
+            + U64_SIZE // redeem_rate
+            + U64_SIZE // claim_rate
+            + PUBKEY_SIZE // reward_coupon
+            + U64_SIZE // reward_cost
+            + BUMP_SIZE
+    }
+}
+
+// Mints `rate * num_tokens` points to the user (the points accounts are optional in instructions that earn points)
+pub fn mint_points<'info>(
+    store_points: &Account<'info, StorePoints>,
+    points_mint: &Option<Account<'info, Mint>>,
+    user_points_token_account: &Option<Account<'info, TokenAccount>>,
+    user: &Account<'info, User>,
+    token_program: &Program<'info, Token>,
+    rate: u64,
+    num_tokens: u64,
+) -> Result<()> {
+    let amount = rate
+        .checked_mul(num_tokens)
+        .ok_or(ChristmasError::PointsOverflow)?;
+    if amount == 0 {
+        return Ok(());
+    }
+    match (points_mint, user_points_token_account) {
+        (Some(mint), Some(to))
+            if mint.key() == store_points.mint
+                && to.mint == store_points.mint
+                && to.owner == user.key() =>
+        {
+            mint_to(
+                CpiContext::new_with_signer(
+                    token_program.to_account_info(),
+                    MintTo {
+                        mint: mint.to_account_info(),
+                        to: to.to_account_info(),
+                        authority: store_points.to_account_info(),
+                    },
+                    &[&[
+                        b"points".as_ref(),
+                        store_points.store.as_ref(),
+                        &[store_points.bump],
+                    ]],
+                ),
+                amount,
+            )
+        }
+        _ => err!(ChristmasError::InvalidPointsAccounts),
+    }
+}
+
+// Sends `num_tokens` coupons to the user's token account, transferred from the region market (or
+// minted if `market` is None, the coupon must be its mint authority), `soulbound` coupons are
+// thawed to receive them and refrozen after
+pub fn send_coupon<'info>(
+    coupon: &Account<'info, Coupon>,
+    mint: &Account<'info, Mint>,
+    to: &AccountInfo<'info>,
+    market: Option<(&Account<'info, RegionMarket>, &Account<'info, TokenAccount>)>,
+    token_program: &Program<'info, Token>,
+    num_tokens: u64,
+) -> Result<()> {
+    let mint_key = mint.key();
+    let coupon_seeds: &[&[&[u8]]] = &[&[b"coupon".as_ref(), mint_key.as_ref(), &[coupon.bump]]];
+
+    // thaw `soulbound` coupons to receive more
+    let soulbound = coupon.options.soulbound;
+    if soulbound && Account::<TokenAccount>::try_from(to)?.is_frozen() {
+        thaw_account(CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            ThawAccount {
+                account: to.clone(),
+                mint: mint.to_account_info(),
+                authority: coupon.to_account_info(),
+            },
+            coupon_seeds,
+        ))?;
+    }
+
+    match market {
+        Some((region_market, from)) => transfer(
+            CpiContext::new_with_signer(
+                token_program.to_account_info(),
+                Transfer {
+                    from: from.to_account_info(),
+                    to: to.clone(),
+                    authority: region_market.to_account_info(),
+                },
+                &[&[
+                    b"market".as_ref(),
+                    &region_market.region,
+                    &[region_market.bump],
+                ]],
+            ),
+            num_tokens,
+        )?,
+        None => mint_to(
+            CpiContext::new_with_signer(
+                token_program.to_account_info(),
+                MintTo {
+                    mint: mint.to_account_info(),
+                    to: to.clone(),
+                    authority: coupon.to_account_info(),
+                },
+                coupon_seeds,
+            ),
+            num_tokens,
+        )?,
+    }
+
+    // freeze `soulbound` coupons so they can't be transferred
+    if soulbound {
+        freeze_account(CpiContext::new_with_signer(
+            token_program.to_account_info(),
+            FreezeAccount {
+                account: to.clone(),
+                mint: mint.to_account_info(),
+                authority: coupon.to_account_info(),
+            },
+            coupon_seeds,
+        ))?;
+    }
+
+    Ok(())
+}
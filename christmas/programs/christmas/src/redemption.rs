@@ -0,0 +1,42 @@
+use anchor_lang::prelude::*;
+
+use crate::defs::*;
+use crate::store::Store;
+
+#[derive(Accounts)]
+pub struct CloseRedemptions<'info> {
+    // redemptions to close are passed in `remaining_accounts` as (redemption, payer) pairs
+    #[account(
+        constraint = store.owner == signer.key(), // only the owner can close receipts
+    )]
+    pub store: Account<'info, Store>,
+    pub signer: Signer<'info>,
+}
+
+#[account]
+pub struct Redemption {
+    pub id: u64, // unique per coupon and user
+    pub coupon: Pubkey,
+    pub store: Pubkey,
+    pub user: Pubkey, // user wallet (signer)
+    pub num_tokens: u64,
+    pub timestamp: u64,    // milliseconds (same as coupon dates)
+    pub reference: String, // merchant supplied (eg. POS order id)
+    pub payer: Pubkey,     // paid the rent, refunded when the receipt is closed
+    pub bump: u8,
+}
+
+impl Redemption {
+    pub fn len() -> usize {
+        DISCRIMINATOR_SIZE
+            + U64_SIZE // id
+            + PUBKEY_SIZE // coupon
+            + PUBKEY_SIZE // store
+            + PUBKEY_SIZE // user
+            + U64_SIZE // num_tokens
+            + DATE_SIZE // timestamp
+            + REFERENCE_SIZE
+            + PUBKEY_SIZE // payer
+            + BUMP_SIZE
+    }
+}
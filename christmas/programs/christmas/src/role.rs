@@ -0,0 +1,84 @@
+use anchor_lang::prelude::*;
+
+use crate::defs::*;
+use crate::store::Store;
+
+// `StoreRole.permissions` bits
+pub const PERMISSION_CREATE_COUPONS: u8 = 1 << 0;
+pub const PERMISSION_MINT_TO_MARKET: u8 = 1 << 1;
+pub const PERMISSION_VERIFY_REDEMPTIONS: u8 = 1 << 2;
+pub const PERMISSION_MANAGE_STAFF: u8 = 1 << 3;
+pub const PERMISSION_ALL: u8 = PERMISSION_CREATE_COUPONS
+    | PERMISSION_MINT_TO_MARKET
+    | PERMISSION_VERIFY_REDEMPTIONS
+    | PERMISSION_MANAGE_STAFF;
+
+#[derive(Accounts)]
+pub struct GrantRole<'info> {
+    pub store: Account<'info, Store>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        seeds = [b"role", store.key().as_ref(), delegate.key().as_ref()],
+        bump,
+        space = StoreRole::len(),
+    )]
+    pub role: Account<'info, StoreRole>,
+    /// CHECK: any wallet can be a delegate
+    pub delegate: UncheckedAccount<'info>,
+    pub signer_role: Option<Account<'info, StoreRole>>, // required if the signer is not the owner
+    pub signer: Signer<'info>,
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct RevokeRole<'info> {
+    pub store: Account<'info, Store>,
+    #[account(
+        mut,
+        seeds = [b"role", store.key().as_ref(), role.delegate.as_ref()],
+        bump = role.bump,
+        close = signer,
+    )]
+    pub role: Account<'info, StoreRole>,
+    pub signer_role: Option<Account<'info, StoreRole>>, // required if the signer is not the owner
+    #[account(mut)]
+    pub signer: Signer<'info>,
+}
+
+#[account]
+pub struct StoreRole {
+    pub store: Pubkey,
+    pub delegate: Pubkey, // wallet acting for the store
+    pub permissions: u8,  // `PERMISSION_*` bits
+    pub epoch: u32,       // `role_epoch` of the store when granted
+    pub bump: u8,
+}
+
+impl StoreRole {
+    pub fn len() -> usize {
+        DISCRIMINATOR_SIZE
+            + PUBKEY_SIZE // store
+            + PUBKEY_SIZE // delegate
+            + U8_SIZE // permissions
+            + U32_SIZE // epoch
+            + BUMP_SIZE
+    }
+}
+
+// Checks `signer` is the store owner or a delegate of the store with `permission` (granted by the
+// current owner)
+pub fn is_authorized(
+    store: &Account<Store>,
+    signer: Pubkey,
+    role: &Option<Account<StoreRole>>,
+    permission: u8,
+) -> bool {
+    signer == store.owner
+        || matches!(role, Some(role) if role.store == store.key()
+            && role.delegate == signer
+            && role.epoch == store.role_epoch
+            && role.permissions & permission == permission)
+}
@@ -1,4 +1,6 @@
-use crate::defs::{BOOL_SIZE, BUMP_SIZE, DISCRIMINATOR_SIZE, U64_SIZE};
+use crate::config::Config;
+use crate::defs::{BOOL_SIZE, BUMP_SIZE, DISCRIMINATOR_SIZE, PUBKEY_SIZE, U64_SIZE};
+use crate::program::Christmas;
 use anchor_lang::prelude::*;
 
 #[derive(Accounts)]
@@ -11,21 +13,85 @@ pub struct Initialize<'info> {
         space = ProgramState::len()
     )]
     pub program_state: Account<'info, ProgramState>,
+    #[account(
+        init_if_needed,
+        seeds = [b"config"],
+        bump,
+        payer = signer,
+        space = Config::len()
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        constraint = program.programdata_address()? == Some(program_data.key()),
+    )]
+    pub program: Program<'info, Christmas>,
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(signer.key()), // only the upgrade authority can initialize
+    )]
+    pub program_data: Account<'info, ProgramData>,
     #[account(mut)]
     pub signer: Signer<'info>,
     pub system_program: Program<'info, System>,
 }
 
+#[derive(Accounts)]
+pub struct MigrateProgramState<'info> {
+    /// CHECK: Program state created before the admin was added, decoded in the instruction
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump,
+    )]
+    pub program_state: UncheckedAccount<'info>,
+    #[account(
+        constraint = program.programdata_address()? == Some(program_data.key()),
+    )]
+    pub program: Program<'info, Christmas>,
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(signer.key()), // only the upgrade authority can migrate
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    #[account(mut)] // pays the rent for the larger account, becomes the admin
+    pub signer: Signer<'info>,
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateProgramState<'info> {
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = program_state.bump,
+        constraint = program_state.admin == signer.key(), // only the admin can update
+    )]
+    pub program_state: Account<'info, ProgramState>,
+    pub signer: Signer<'info>,
+}
+
 #[account]
 pub struct ProgramState {
     // Used to store global/shared state
     pub is_initialized: bool,
-    pub store_counter: u64,
+    pub store_counter: u64, // total stores ever created (used for store ids)
+    pub active_stores: u64, // stores created and not yet closed
+    pub admin: Pubkey,
+    pub paused: bool, // blocks instructions moving tokens or creating stores/coupons
     pub bump: u8,
 }
 
 impl ProgramState {
-    fn len() -> usize {
+    pub fn len() -> usize {
+        DISCRIMINATOR_SIZE
+            + BOOL_SIZE // is_initialized
+            + U64_SIZE // store_counter
+            + U64_SIZE // active_stores
+            + PUBKEY_SIZE // admin
+            + BOOL_SIZE // paused
+            + BUMP_SIZE
+    }
+
+    // Program state created before `active_stores`, `admin` and `paused` were added
+    pub fn legacy_len() -> usize {
         DISCRIMINATOR_SIZE + BOOL_SIZE + U64_SIZE + BUMP_SIZE
     }
 }
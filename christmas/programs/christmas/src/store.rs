@@ -1,6 +1,6 @@
 use anchor_lang::prelude::*;
 
-use crate::{defs::*, state::ProgramState};
+use crate::{config::Config, defs::*, state::ProgramState};
 
 #[derive(Accounts)]
 #[instruction(name: String, id: u64)]
@@ -24,17 +24,117 @@ pub struct CreateStore<'info> {
         bump = state.bump
     )]
     pub state: Account<'info, ProgramState>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>, // checked for allowed regions
+    pub system_program: Program<'info, System>,
+}
+
+#[derive(Accounts)]
+pub struct UpdateStore<'info> {
+    #[account(
+        mut,
+        constraint = store.owner == signer.key(), // only the owner can update
+    )]
+    pub store: Account<'info, Store>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>, // checked for allowed regions
+    pub signer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct CloseStore<'info> {
+    #[account(
+        mut,
+        constraint = store.owner == signer.key(), // only the owner can close
+        close = signer, // return rent to the owner
+    )]
+    pub store: Account<'info, Store>,
+    /// CHECK: The store's treasury, may not have been created (swept to the owner if it was)
+    #[account(
+        mut,
+        seeds = [b"treasury", store.key().as_ref()],
+        bump,
+    )]
+    pub treasury: UncheckedAccount<'info>,
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    #[account(
+        mut,
+        seeds = [b"state"],
+        bump = state.bump
+    )]
+    pub state: Account<'info, ProgramState>,
+}
+
+#[derive(Accounts)]
+pub struct ProposeStoreOwner<'info> {
+    #[account(
+        mut,
+        constraint = store.owner == signer.key(), // only the owner can propose a new owner
+    )]
+    pub store: Account<'info, Store>,
+    pub signer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct AcceptStoreOwner<'info> {
+    #[account(
+        mut,
+        constraint = store.pending_owner == signer.key(), // only the proposed owner can accept
+    )]
+    pub store: Account<'info, Store>,
+    pub signer: Signer<'info>,
+}
+
+#[derive(Accounts)]
+pub struct MigrateStore<'info> {
+    /// CHECK: Store created before `live_coupons`, `pending_owner` and `role_epoch` were added, decoded in the instruction
+    #[account(mut)]
+    pub store: UncheckedAccount<'info>,
+    #[account(
+        seeds = [b"state"],
+        bump = state.bump,
+        constraint = state.admin == signer.key(), // only the admin can migrate
+    )]
+    pub state: Account<'info, ProgramState>,
+    #[account(mut)] // pays the rent for the larger account
+    pub signer: Signer<'info>,
     pub system_program: Program<'info, System>,
 }
 
+#[event]
+pub struct StoreOwnerChanged {
+    pub store: Pubkey,
+    pub previous_owner: Pubkey,
+    pub owner: Pubkey,
+}
+
+#[event]
+pub struct StoreUpdated {
+    pub store: Pubkey,
+    pub name: String,
+    pub region: [u8; 3],
+    pub geohash: [u8; 6],
+    pub uri: String,
+}
+
 #[account]
 pub struct Store {
     pub id: u64, // unique to each store, check `store_counter` in `state`
     pub name: String,
     pub region: [u8; 3],
     pub geohash: [u8; 6],
-    pub uri: String,   // to the json metadata
-    pub owner: Pubkey, // set to signer
+    pub uri: String,           // to the json metadata
+    pub owner: Pubkey,         // set to signer (not in the seeds after a transfer)
+    pub live_coupons: u64,     // coupons created and not yet closed
+    pub pending_owner: Pubkey, // proposed owner, Pubkey::default() for none
+    pub role_epoch: u32,       // bumped on owner changes, roles granted in earlier epochs are void
     pub bump: u8,
 }
 
@@ -47,6 +147,14 @@ impl Store {
             + GEOHASH_SIZE
             + URI_SIZE
             + PUBKEY_SIZE
+            + U64_SIZE // live_coupons
+            + PUBKEY_SIZE // pending_owner
+            + U32_SIZE // role_epoch
             + BUMP_SIZE
     }
+
+    // Stores created before `live_coupons`, `pending_owner` and `role_epoch` were added
+    pub fn legacy_len() -> usize {
+        Self::len() - U64_SIZE - PUBKEY_SIZE - U32_SIZE
+    }
 }
@@ -0,0 +1,252 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::spl_token;
+use solana_program::bpf_loader_upgradeable;
+use solana_program::entrypoint::{ProgramResult, MAX_PERMITTED_DATA_INCREASE, SUCCESS};
+use solana_program::instruction::Instruction;
+use solana_program::program_option::COption;
+use solana_program::program_pack::Pack;
+use solana_program::program_stubs::{set_syscall_stubs, SyscallStubs};
+use std::cell::{Cell, RefCell};
+use std::sync::Once;
+
+thread_local! {
+    // each test runs on its own thread, so each test has its own clock
+    static UNIX_TIMESTAMP: Cell<i64> = const { Cell::new(0) };
+    static CPIS: RefCell<Vec<Instruction>> = const { RefCell::new(vec![]) };
+}
+
+struct TestStubs;
+
+impl SyscallStubs for TestStubs {
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        _account_infos: &[AccountInfo],
+        _signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        CPIS.with(|cpis| cpis.borrow_mut().push(instruction.clone()));
+        Ok(())
+    }
+
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        let clock = Clock {
+            unix_timestamp: UNIX_TIMESTAMP.with(|t| t.get()),
+            ..Clock::default()
+        };
+        unsafe {
+            *(var_addr as *mut Clock) = clock;
+        }
+        SUCCESS
+    }
+
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        unsafe {
+            *(var_addr as *mut Rent) = Rent::default();
+        }
+        SUCCESS
+    }
+}
+
+// Sets the `Clock` sysvar (in seconds) seen by instruction handlers on the current test thread
+pub fn warp_to(unix_timestamp: i64) {
+    static STUBS: Once = Once::new();
+    STUBS.call_once(|| {
+        set_syscall_stubs(Box::new(TestStubs));
+    });
+    UNIX_TIMESTAMP.with(|t| t.set(unix_timestamp));
+}
+
+// Returns the CPIs made on the current test thread since the last call (call `warp_to` first)
+pub fn take_cpis() -> Vec<Instruction> {
+    CPIS.with(|cpis| cpis.take())
+}
+
+/*
+    Backing storage for `AccountInfo`s so instruction handlers can be called directly in unit tests
+    (CPIs are no-ops outside of the runtime). Data is laid out like the runtime's input buffer so
+    `AccountInfo::realloc` (also used to close accounts) works.
+*/
+#[repr(C)] // `realloc` reads the original data length from the 4 bytes before the key
+pub struct TestAccount {
+    _padding: u32,
+    original_data_len: u32,
+    pub key: Pubkey,
+    pub lamports: u64,
+    buffer: Vec<u64>, // data length, data, then room for `MAX_PERMITTED_DATA_INCREASE`
+    pub owner: Pubkey,
+    pub is_signer: bool,
+    pub executable: bool,
+}
+
+impl TestAccount {
+    fn new(
+        key: Pubkey,
+        lamports: u64,
+        data: &[u8],
+        owner: Pubkey,
+        is_signer: bool,
+        executable: bool,
+    ) -> Self {
+        let mut account = TestAccount {
+            _padding: 0,
+            original_data_len: 0,
+            key,
+            lamports,
+            buffer: vec![data.len() as u64],
+            owner,
+            is_signer,
+            executable,
+        };
+        account.reserve_realloc();
+        account.data_mut().copy_from_slice(data);
+        account
+    }
+
+    pub fn new_signer() -> Self {
+        Self::new(
+            Pubkey::new_unique(),
+            1_000_000_000,
+            &[],
+            System::id(),
+            true,
+            false,
+        )
+    }
+
+    // An account that has not been created yet
+    pub fn new_empty() -> Self {
+        Self::new(Pubkey::new_unique(), 0, &[], System::id(), false, false)
+    }
+
+    pub fn new_program(program_id: Pubkey) -> Self {
+        Self::new(program_id, 1, &[], Pubkey::default(), false, true)
+    }
+
+    // Program and program data accounts as laid out (bincode) by the upgradeable BPF loader
+    pub fn new_upgradeable_program(program_id: Pubkey, upgrade_authority: Pubkey) -> (Self, Self) {
+        let program_data = Pubkey::new_unique();
+
+        // `UpgradeableLoaderState::Program { programdata_address }`
+        let mut data = 2u32.to_le_bytes().to_vec();
+        data.extend_from_slice(program_data.as_ref());
+        let program = Self::new(
+            program_id,
+            1,
+            &data,
+            bpf_loader_upgradeable::ID,
+            false,
+            true,
+        );
+
+        // `UpgradeableLoaderState::ProgramData { slot, upgrade_authority_address }`
+        let mut data = 3u32.to_le_bytes().to_vec();
+        data.extend_from_slice(&0u64.to_le_bytes());
+        data.push(1);
+        data.extend_from_slice(upgrade_authority.as_ref());
+        let program_data = Self::new(
+            program_data,
+            1_000_000,
+            &data,
+            bpf_loader_upgradeable::ID,
+            false,
+            false,
+        );
+
+        (program, program_data)
+    }
+
+    pub fn new_account<T: AccountSerialize>(account: &T) -> Self {
+        Self::new_account_with_key(Pubkey::new_unique(), account)
+    }
+
+    pub fn new_account_with_key<T: AccountSerialize>(key: Pubkey, account: &T) -> Self {
+        let mut data: Vec<u8> = vec![];
+        account.try_serialize(&mut data).unwrap();
+        Self::new(key, 1_000_000, &data, crate::ID, false, false)
+    }
+
+    pub fn new_mint(authority: Pubkey, supply: u64) -> Self {
+        let mut data = vec![0u8; spl_token::state::Mint::LEN];
+        spl_token::state::Mint {
+            mint_authority: COption::Some(authority),
+            supply,
+            decimals: 0,
+            is_initialized: true,
+            freeze_authority: COption::Some(authority),
+        }
+        .pack_into_slice(&mut data);
+        Self::new(
+            Pubkey::new_unique(),
+            1_000_000,
+            &data,
+            spl_token::ID,
+            false,
+            false,
+        )
+    }
+
+    pub fn new_token_account(mint: Pubkey, owner: Pubkey, amount: u64) -> Self {
+        let mut data = vec![0u8; spl_token::state::Account::LEN];
+        spl_token::state::Account {
+            mint,
+            owner,
+            amount,
+            delegate: COption::None,
+            state: spl_token::state::AccountState::Initialized,
+            is_native: COption::None,
+            delegated_amount: 0,
+            close_authority: COption::None,
+        }
+        .pack_into_slice(&mut data);
+        Self::new(
+            Pubkey::new_unique(),
+            1_000_000,
+            &data,
+            spl_token::ID,
+            false,
+            false,
+        )
+    }
+
+    pub fn freeze_token_account(mut self) -> Self {
+        let mut account = spl_token::state::Account::unpack_from_slice(self.data()).unwrap();
+        account.state = spl_token::state::AccountState::Frozen;
+        account.pack_into_slice(self.data_mut());
+        self
+    }
+
+    pub fn data(&self) -> &[u8] {
+        let len = self.buffer[0] as usize;
+        unsafe { std::slice::from_raw_parts(self.buffer[1..].as_ptr() as *const u8, len) }
+    }
+
+    pub fn data_mut(&mut self) -> &mut [u8] {
+        let len = self.buffer[0] as usize;
+        unsafe { std::slice::from_raw_parts_mut(self.buffer[1..].as_mut_ptr() as *mut u8, len) }
+    }
+
+    // Grows the buffer so the data can be reallocated by up to `MAX_PERMITTED_DATA_INCREASE`
+    fn reserve_realloc(&mut self) {
+        let len = self.buffer[0] as usize + MAX_PERMITTED_DATA_INCREASE;
+        self.buffer.resize(2 + len / 8, 0);
+    }
+
+    pub fn info(&mut self) -> AccountInfo<'_> {
+        self.reserve_realloc();
+        self.original_data_len = self.buffer[0] as u32;
+        let len = self.buffer[0] as usize;
+        let data = unsafe {
+            std::slice::from_raw_parts_mut(self.buffer[1..].as_mut_ptr() as *mut u8, len)
+        };
+        AccountInfo::new(
+            &self.key,
+            self.is_signer,
+            true,
+            &mut self.lamports,
+            data,
+            &self.owner,
+            self.executable,
+            0,
+        )
+    }
+}
@@ -0,0 +1,117 @@
+use super::*;
+
+#[test]
+fn test_set_config() {
+    let mut signer = TestAccount::new_signer();
+    let mut config = TestAccount::new_account(&new_config());
+    let mut state = TestAccount::new_account(&ProgramState {
+        admin: signer.key,
+        ..new_state()
+    });
+
+    let mut accounts = UpdateConfig {
+        config: Account::try_from(&config.info()).unwrap(),
+        state: Account::try_from(&state.info()).unwrap(),
+        signer: Signer::try_from(&signer.info()).unwrap(),
+    };
+
+    let err = christmas::set_config(
+        Context::new(&crate::ID, &mut accounts, &[], BTreeMap::new()),
+        100,
+        2,
+        MAX_FEE_RATE + 1,
+        Pubkey::default(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::InvalidFeeRate.into());
+
+    let err = christmas::set_config(
+        Context::new(&crate::ID, &mut accounts, &[], BTreeMap::new()),
+        100,
+        2,
+        250,
+        Pubkey::default(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::InvalidFeeRecipient.into());
+
+    let fee_recipient = Pubkey::new_unique();
+    christmas::set_config(
+        Context::new(&crate::ID, &mut accounts, &[], BTreeMap::new()),
+        100,
+        2,
+        250,
+        fee_recipient,
+    )
+    .unwrap();
+    assert_eq!(accounts.config.max_mint_per_call, 100);
+    assert_eq!(accounts.config.max_coupons_per_store, 2);
+    assert_eq!(accounts.config.fee_rate, 250);
+    assert_eq!(accounts.config.fee_recipient, fee_recipient);
+    assert_eq!(accounts.config.fee(1_000), 25);
+    assert!(accounts.config.validate_mint(100).is_ok());
+    assert_eq!(
+        accounts.config.validate_mint(101).unwrap_err(),
+        ChristmasError::MintLimitExceeded.into()
+    );
+    assert!(accounts.config.validate_live_coupons(1).is_ok());
+    assert_eq!(
+        accounts.config.validate_live_coupons(2).unwrap_err(),
+        ChristmasError::MaxCouponsPerStoreExceeded.into()
+    );
+
+    christmas::set_region_enabled(
+        Context::new(&crate::ID, &mut accounts, &[], BTreeMap::new()),
+        *b"SGP",
+        false,
+    )
+    .unwrap();
+    assert_eq!(
+        accounts.config.validate_region(b"SGP").unwrap_err(),
+        ChristmasError::RegionDisabled.into()
+    );
+    assert!(accounts.config.validate_region(b"USA").is_ok());
+    assert_eq!(
+        accounts.config.validate_region(b"XYZ").unwrap_err(),
+        ChristmasError::InvalidRegion.into()
+    );
+
+    christmas::set_region_enabled(
+        Context::new(&crate::ID, &mut accounts, &[], BTreeMap::new()),
+        *b"SGP",
+        true,
+    )
+    .unwrap();
+    assert!(accounts.config.validate_region(b"SGP").is_ok());
+}
+
+#[test]
+fn test_set_config_not_admin() {
+    let admin = Pubkey::new_unique();
+    let mut signer = TestAccount::new_signer();
+    let (config_key, config_bump) = find_pda(&[b"config"]);
+    let mut config = TestAccount::new_account_with_key(
+        config_key,
+        &Config {
+            bump: config_bump,
+            ..new_config()
+        },
+    );
+    let (state_key, state_bump) = find_pda(&[b"state"]);
+    let mut state = TestAccount::new_account_with_key(
+        state_key,
+        &ProgramState {
+            admin,
+            bump: state_bump,
+            ..new_state()
+        },
+    );
+
+    let err = try_accounts::<UpdateConfig>(&[config.info(), state.info(), signer.info()])
+        .map(|_| ())
+        .unwrap_err();
+    assert_eq!(err, ErrorCode::ConstraintRaw.into());
+
+    signer.key = admin;
+    assert!(try_accounts::<UpdateConfig>(&[config.info(), state.info(), signer.info()]).is_ok());
+}
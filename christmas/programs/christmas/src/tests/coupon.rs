@@ -0,0 +1,548 @@
+use super::*;
+
+#[test]
+fn test_create_coupon_twice() {
+    let mut fixture = CreateCouponFixture::new();
+    let mut accounts = fixture.accounts();
+
+    christmas::create_coupon(
+        Context::new(&crate::ID, &mut accounts, &[], CreateCouponFixture::bumps()),
+        "coupon".to_string(),
+        *b"SGP",
+        *b"w21z3w",
+        "https://coupon.com".to_string(),
+        1_704_067_200_000,
+        1_706_745_600_000,
+        CouponOptions::default(),
+        CouponTerms::default(),
+    )
+    .unwrap();
+    assert_eq!(accounts.store.live_coupons, 1);
+    accounts.coupon.supply = 10;
+    accounts.coupon.has_supply = true;
+
+    let err = christmas::create_coupon(
+        Context::new(&crate::ID, &mut accounts, &[], CreateCouponFixture::bumps()),
+        "overwritten".to_string(),
+        *b"SGP",
+        *b"w21z98",
+        "https://overwritten.com".to_string(),
+        1_706_745_600_000,
+        1_709_251_200_000,
+        CouponOptions {
+            cosigned_redeem: true,
+            ..CouponOptions::default()
+        },
+        CouponTerms::default(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::CouponAlreadyExists.into());
+    assert!(accounts.coupon.name.starts_with("coupon\0"));
+    assert_eq!(accounts.coupon.geohash, *b"w21z3w");
+    assert_eq!(accounts.coupon.valid_from, 1_704_067_200_000);
+    assert_eq!(accounts.coupon.valid_to, 1_706_745_600_000);
+    assert_eq!(accounts.coupon.supply, 10);
+    assert!(accounts.coupon.has_supply);
+    assert!(!accounts.coupon.options.cosigned_redeem);
+    assert_eq!(accounts.store.live_coupons, 1);
+}
+
+#[test]
+fn test_create_coupon_other_region_market() {
+    let mut fixture = CreateCouponFixture::new();
+    let mut accounts = fixture.accounts();
+    accounts.region_market.region = *b"MYS";
+
+    let err = christmas::create_coupon(
+        Context::new(&crate::ID, &mut accounts, &[], CreateCouponFixture::bumps()),
+        "coupon".to_string(),
+        *b"SGP",
+        *b"w21z3w",
+        "https://coupon.com".to_string(),
+        1_704_067_200_000,
+        1_706_745_600_000,
+        CouponOptions::default(),
+        CouponTerms::default(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::MarketMismatch.into());
+}
+
+#[test]
+fn test_create_coupon_terms() {
+    let mut fixture = CreateCouponFixture::new();
+    let mut accounts = fixture.accounts();
+
+    let err = christmas::create_coupon(
+        Context::new(&crate::ID, &mut accounts, &[], CreateCouponFixture::bumps()),
+        "coupon".to_string(),
+        *b"SGP",
+        *b"w21z3w",
+        "https://coupon.com".to_string(),
+        1_704_067_200_000,
+        1_706_745_600_000,
+        CouponOptions::default(),
+        CouponTerms {
+            value: 10_001, // more than 100%
+            ..CouponTerms::default()
+        },
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::InvalidTerms.into());
+
+    christmas::create_coupon(
+        Context::new(&crate::ID, &mut accounts, &[], CreateCouponFixture::bumps()),
+        "coupon".to_string(),
+        *b"SGP",
+        *b"w21z3w",
+        "https://coupon.com".to_string(),
+        1_704_067_200_000,
+        1_706_745_600_000,
+        CouponOptions::default(),
+        CouponTerms {
+            discount_type: DiscountType::FixedAmount,
+            value: 500,
+            currency: *b"SGD",
+            min_spend: 2_000,
+            terms_hash: [1; 32],
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        accounts.coupon.terms.discount_type,
+        DiscountType::FixedAmount
+    );
+    assert_eq!(accounts.coupon.terms.value, 500);
+    assert_eq!(accounts.coupon.terms.min_spend, 2_000);
+    assert_eq!(accounts.coupon.terms.currency, *b"SGD");
+    assert_eq!(accounts.coupon.terms.terms_hash, [1; 32]);
+}
+
+#[test]
+fn test_update_coupon_not_update_authority() {
+    let mut owner = TestAccount::new_signer();
+    let mut update_authority = TestAccount::new_signer();
+    let mut signer = TestAccount::new_signer();
+    let mut store = TestAccount::new_account(&Store {
+        owner: owner.key,
+        bump: 254,
+        ..new_store()
+    });
+    let mint = Pubkey::new_unique();
+    let (coupon_key, coupon_bump) = find_pda(&[b"coupon", mint.as_ref()]);
+    let mut coupon = TestAccount::new_account_with_key(
+        coupon_key,
+        &Coupon {
+            update_authority: update_authority.key,
+            mint,
+            store: store.key,
+            bump: coupon_bump,
+            ..new_coupon()
+        },
+    );
+
+    let err = try_accounts::<UpdateCoupon>(&[coupon.info(), store.info(), signer.info()])
+        .map(|_| ())
+        .unwrap_err();
+    assert_eq!(err, ErrorCode::ConstraintRaw.into());
+
+    // the delegated update authority and the (current) store owner can update
+    assert!(
+        try_accounts::<UpdateCoupon>(&[coupon.info(), store.info(), update_authority.info()])
+            .is_ok()
+    );
+    assert!(try_accounts::<UpdateCoupon>(&[coupon.info(), store.info(), owner.info()]).is_ok());
+}
+#[test]
+fn test_update_coupon() {
+    let mut signer = TestAccount::new_signer();
+    let mut coupon = Coupon {
+        update_authority: signer.key,
+        name: "coupon".to_string(),
+        geohash: *b"w21z3w",
+        bump: 254,
+        ..new_coupon()
+    };
+    coupon.set_validity(1_704_067_200_000, 1_706_745_600_000);
+    let mut coupon = TestAccount::new_account(&coupon);
+
+    let mut store = TestAccount::new_account(&new_store());
+    let mut accounts = UpdateCoupon {
+        coupon: Account::try_from(&coupon.info()).unwrap(),
+        store: Account::try_from(&store.info()).unwrap(),
+        signer: Signer::try_from(&signer.info()).unwrap(),
+    };
+
+    let err = christmas::update_coupon(
+        Context::new(&crate::ID, &mut accounts, &[], BTreeMap::new()),
+        "coupon".to_string(),
+        *b"w21z3w",
+        "".to_string(),
+        1_706_745_600_000,
+        1_704_067_200_000,
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::InvalidDateRange.into());
+    assert_eq!(accounts.coupon.valid_to, 1_706_745_600_000);
+
+    // extend validity past the date hash epoch boundary (256 days after 1 jan 2024)
+    let valid_from = 1_725_148_800_000; // 1 sep 2024
+    let valid_to = 1_735_689_600_000; // 1 jan 2025
+    christmas::update_coupon(
+        Context::new(&crate::ID, &mut accounts, &[], BTreeMap::new()),
+        "renamed".to_string(),
+        *b"w21z98",
+        "https://renamed.com".to_string(),
+        valid_from,
+        valid_to,
+    )
+    .unwrap();
+    assert!(accounts.coupon.name.starts_with("renamed\0"));
+    assert_eq!(accounts.coupon.geohash, *b"w21z98");
+    assert_eq!(accounts.coupon.valid_from, valid_from);
+    assert_eq!(accounts.coupon.valid_to, valid_to);
+    assert_eq!(
+        accounts.coupon.valid_from_hash,
+        days_to_byte_mask(epoch_days_from_date(valid_from))
+    );
+    assert_eq!(
+        accounts.coupon.valid_to_hash,
+        days_to_byte_mask(epoch_days_from_date(valid_to))
+    );
+    assert!(accounts.coupon.datehash_overflow);
+}
+
+#[test]
+fn test_set_coupon_update_authority() {
+    let mut signer = TestAccount::new_signer();
+    let mut coupon = TestAccount::new_account(&Coupon {
+        update_authority: signer.key,
+        bump: 254,
+        ..new_coupon()
+    });
+
+    let mut store = TestAccount::new_account(&new_store());
+    let mut accounts = UpdateCoupon {
+        coupon: Account::try_from(&coupon.info()).unwrap(),
+        store: Account::try_from(&store.info()).unwrap(),
+        signer: Signer::try_from(&signer.info()).unwrap(),
+    };
+
+    let multisig = Pubkey::new_unique();
+    christmas::set_coupon_update_authority(
+        Context::new(&crate::ID, &mut accounts, &[], BTreeMap::new()),
+        multisig,
+    )
+    .unwrap();
+    assert_eq!(accounts.coupon.update_authority, multisig);
+}
+
+#[test]
+fn test_migrate_mint_authority() {
+    let mut signer = TestAccount::new_signer();
+    let mut mint = TestAccount::new_mint(signer.key, 10);
+    let update_authority = Pubkey::new_unique();
+    let mut coupon = TestAccount::new_account(&Coupon {
+        update_authority,
+        mint: mint.key,
+        bump: 254,
+        ..new_coupon()
+    });
+    let mut token_program = TestAccount::new_program(anchor_spl::token::ID);
+    let mut system_program = TestAccount::new_program(System::id());
+
+    let mut accounts = MigrateMintAuthority {
+        coupon: UncheckedAccount::try_from(coupon.info()),
+        mint: Account::try_from(&mint.info()).unwrap(),
+        signer: Signer::try_from(&signer.info()).unwrap(),
+        token_program: Program::try_from(&token_program.info()).unwrap(),
+        system_program: Program::try_from(&system_program.info()).unwrap(),
+    };
+    warp_to(1_705_000_000);
+
+    christmas::migrate_mint_authority(Context::new(
+        &crate::ID,
+        &mut accounts,
+        &[],
+        BTreeMap::new(),
+    ))
+    .unwrap();
+    let coupon_key = Some(accounts.coupon.key());
+    assert_eq!(
+        token_instructions(&take_cpis()),
+        vec![
+            TokenInstruction::SetAuthority {
+                authority_type: AuthorityType::MintTokens,
+                new_authority: coupon_key.into(),
+            },
+            TokenInstruction::SetAuthority {
+                authority_type: AuthorityType::FreezeAccount,
+                new_authority: coupon_key.into(),
+            }
+        ]
+    );
+
+    // a delegated update authority is kept
+    let migrated = Account::<Coupon>::try_from(&accounts.coupon).unwrap();
+    assert_eq!(migrated.update_authority, update_authority);
+}
+
+#[test]
+fn test_migrate_mint_authority_legacy_coupon() {
+    let mut signer = TestAccount::new_signer();
+    let mut mint = TestAccount::new_mint(signer.key, 10);
+    // names and uris are padded so coupons have a fixed layout
+    let store = Pubkey::new_unique();
+    let mut coupon = TestAccount::new_account(&Coupon {
+        update_authority: signer.key,
+        mint: mint.key,
+        name: pad_string(
+            "coupon",
+            COUPON_NAME_SIZE - STRING_PREFIX_SIZE,
+            ChristmasError::NameTooLong,
+        )
+        .unwrap(),
+        uri: pad_string(
+            "",
+            URI_SIZE - STRING_PREFIX_SIZE,
+            ChristmasError::UriTooLong,
+        )
+        .unwrap(),
+        store,
+        supply: 10,
+        region: *b"SGP",
+        bump: 254,
+        ..new_coupon()
+    });
+    // coupons created before options and terms were added end with the bump
+    coupon.data_mut()[Coupon::legacy_len() - 1] = 254;
+    coupon.info().realloc(Coupon::legacy_len(), false).unwrap();
+    let mut token_program = TestAccount::new_program(anchor_spl::token::ID);
+    let mut system_program = TestAccount::new_program(System::id());
+
+    let mut accounts = MigrateMintAuthority {
+        coupon: UncheckedAccount::try_from(coupon.info()),
+        mint: Account::try_from(&mint.info()).unwrap(),
+        signer: Signer::try_from(&signer.info()).unwrap(),
+        token_program: Program::try_from(&token_program.info()).unwrap(),
+        system_program: Program::try_from(&system_program.info()).unwrap(),
+    };
+    assert!(Account::<Coupon>::try_from(&accounts.coupon).is_err());
+    warp_to(1_705_000_000);
+
+    christmas::migrate_mint_authority(Context::new(
+        &crate::ID,
+        &mut accounts,
+        &[],
+        BTreeMap::new(),
+    ))
+    .unwrap();
+    let cpis = take_cpis();
+    assert_eq!(
+        cpis[0],
+        solana_program::system_instruction::transfer(
+            &accounts.signer.key(),
+            &accounts.coupon.key(),
+            Rent::default().minimum_balance(Coupon::len()) - 1_000_000
+        )
+    );
+    assert_eq!(token_instructions(&cpis[1..]).len(), 2);
+
+    let migrated = Account::<Coupon>::try_from(&accounts.coupon).unwrap();
+    assert_eq!(migrated.mint, accounts.mint.key());
+    assert_eq!(migrated.supply, 10);
+    assert_eq!(migrated.region, *b"SGP");
+    assert_eq!(migrated.options.max_supply, 0);
+    assert_eq!(migrated.terms.value, 0);
+    assert_eq!(migrated.bump, 254);
+
+    // the store takes over from the merchant wallet as update authority
+    assert_eq!(migrated.update_authority, store);
+}
+
+#[test]
+fn test_close_coupon() {
+    let mut update_authority = TestAccount::new_signer();
+    let mut anyone = TestAccount::new_signer();
+    let mut store_owner = TestAccount::new_signer();
+    let mut mint = TestAccount::new_mint(update_authority.key, 10);
+    let mut region_market = TestAccount::new_account(&RegionMarket {
+        region: *b"SGP",
+        bump: 254,
+    });
+    let mut region_market_token_account =
+        TestAccount::new_token_account(mint.key, region_market.key, 10);
+    let mut store = TestAccount::new_account(&Store {
+        owner: store_owner.key,
+        live_coupons: 2,
+        bump: 254,
+        ..new_store()
+    });
+    let mut coupon = TestAccount::new_account(&Coupon {
+        update_authority: update_authority.key,
+        mint: mint.key,
+        store: store.key,
+        region: *b"SGP",
+        valid_from: 1_704_067_200_000, // 1 jan 2024
+        valid_to: 1_706_745_600_000,   // 1 feb 2024
+        bump: 254,
+        ..new_coupon()
+    });
+    let mut token_program = TestAccount::new_program(anchor_spl::token::ID);
+
+    let mut state = TestAccount::new_account(&new_state());
+    let mut accounts = CloseCoupon {
+        coupon: Account::try_from(&coupon.info()).unwrap(),
+        mint: Account::try_from(&mint.info()).unwrap(),
+        region_market: Account::try_from(&region_market.info()).unwrap(),
+        region_market_token_account: Account::try_from(&region_market_token_account.info())
+            .unwrap(),
+        store: Account::try_from(&store.info()).unwrap(),
+        store_owner: UncheckedAccount::try_from(store_owner.info()),
+        state: Account::try_from(&state.info()).unwrap(),
+        signer: Signer::try_from(&anyone.info()).unwrap(),
+        token_program: Program::try_from(&token_program.info()).unwrap(),
+    };
+
+    // anyone can't close the coupon before it expires
+    warp_to(1_706_745_600); // 1 feb 2024
+    let err = christmas::close_coupon(Context::new(
+        &crate::ID,
+        &mut accounts,
+        &[],
+        BTreeMap::new(),
+    ))
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::CouponNotExpired.into());
+    assert_eq!(accounts.store.live_coupons, 2);
+
+    // anyone can close the coupon after it expires
+    warp_to(1_706_745_601);
+    christmas::close_coupon(Context::new(
+        &crate::ID,
+        &mut accounts,
+        &[],
+        BTreeMap::new(),
+    ))
+    .unwrap();
+    assert_eq!(accounts.store.live_coupons, 1);
+
+    // update authority can close the coupon at any time
+    warp_to(1_704_067_200); // 1 jan 2024
+    accounts.signer = Signer::try_from(&update_authority.info()).unwrap();
+    christmas::close_coupon(Context::new(
+        &crate::ID,
+        &mut accounts,
+        &[],
+        BTreeMap::new(),
+    ))
+    .unwrap();
+    assert_eq!(accounts.store.live_coupons, 0);
+}
+
+#[test]
+fn test_transfer_coupon() {
+    let mut signer = TestAccount::new_signer();
+    let mut payer = TestAccount::new_signer();
+    let mut recipient = TestAccount::new_signer();
+    let mut mint = TestAccount::new_mint(Pubkey::new_unique(), 10);
+    let mut coupon = TestAccount::new_account(&Coupon {
+        mint: mint.key,
+        region: *b"SGP",
+        bump: 254,
+        ..new_coupon()
+    });
+    let mut user = TestAccount::new_account(&User {
+        region: *b"SGP",
+        bump: 254,
+        ..new_user()
+    });
+    let mut user_token_account = TestAccount::new_token_account(mint.key, user.key, 10);
+    let mut uses = TestAccount::new_empty();
+    let mut started_uses = TestAccount::new_account(&CouponUses {
+        remaining: 2,
+        bump: 254,
+    });
+    let mut recipient_user = TestAccount::new_account(&new_user());
+    let mut recipient_token_account =
+        TestAccount::new_token_account(mint.key, recipient_user.key, 0);
+    let mut associated_token_program = TestAccount::new_program(anchor_spl::associated_token::ID);
+    let mut token_program = TestAccount::new_program(anchor_spl::token::ID);
+    let mut system_program = TestAccount::new_program(System::id());
+
+    let mut state = TestAccount::new_account(&new_state());
+    let mut accounts = TransferCoupon {
+        coupon: Account::try_from(&coupon.info()).unwrap(),
+        mint: Account::try_from(&mint.info()).unwrap(),
+        user: Account::try_from(&user.info()).unwrap(),
+        user_token_account: Account::try_from(&user_token_account.info()).unwrap(),
+        uses: None,
+        recipient: UncheckedAccount::try_from(recipient.info()),
+        recipient_user: Account::try_from(&recipient_user.info()).unwrap(),
+        recipient_token_account: Account::try_from(&recipient_token_account.info()).unwrap(),
+        state: Account::try_from(&state.info()).unwrap(),
+        signer: Signer::try_from(&signer.info()).unwrap(),
+        payer: Signer::try_from(&payer.info()).unwrap(),
+        associated_token_program: Program::try_from(&associated_token_program.info()).unwrap(),
+        token_program: Program::try_from(&token_program.info()).unwrap(),
+        system_program: Program::try_from(&system_program.info()).unwrap(),
+    };
+
+    let err = christmas::transfer_coupon(
+        Context::new(&crate::ID, &mut accounts, &[], bumps(&["recipient_user"])),
+        1,
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::CouponNotTransferable.into());
+    assert_eq!(accounts.recipient_user.bump, 0);
+
+    accounts.coupon.options.transferable = true;
+    christmas::transfer_coupon(
+        Context::new(&crate::ID, &mut accounts, &[], bumps(&["recipient_user"])),
+        1,
+    )
+    .unwrap();
+    assert_eq!(accounts.recipient_user.region, *b"SGP");
+    assert_eq!(accounts.recipient_user.bump, 254);
+
+    // multi-use coupons need the sender's uses
+    accounts.coupon.options.uses_per_token = 3;
+    let err = christmas::transfer_coupon(
+        Context::new(&crate::ID, &mut accounts, &[], bumps(&["recipient_user"])),
+        1,
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::MissingUsesAccount.into());
+
+    // all tokens can be given away if none were redeemed
+    accounts.uses = Some(UncheckedAccount::try_from(uses.info()));
+    christmas::transfer_coupon(
+        Context::new(&crate::ID, &mut accounts, &[], bumps(&["recipient_user"])),
+        10,
+    )
+    .unwrap();
+
+    // but not the partly used token
+    accounts.uses = Some(UncheckedAccount::try_from(started_uses.info()));
+    let err = christmas::transfer_coupon(
+        Context::new(&crate::ID, &mut accounts, &[], bumps(&["recipient_user"])),
+        10,
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::CouponPartlyUsed.into());
+    christmas::transfer_coupon(
+        Context::new(&crate::ID, &mut accounts, &[], bumps(&["recipient_user"])),
+        9,
+    )
+    .unwrap();
+    accounts.coupon.options.uses_per_token = 0;
+
+    // `soulbound` overrides `transferable`
+    accounts.coupon.options.soulbound = true;
+    let err = christmas::transfer_coupon(
+        Context::new(&crate::ID, &mut accounts, &[], bumps(&["recipient_user"])),
+        1,
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::CouponNotTransferable.into());
+}
@@ -0,0 +1,197 @@
+use super::*;
+
+#[test]
+fn test_set_loyalty_program() {
+    let mut signer = TestAccount::new_signer();
+    let mut payer = TestAccount::new_signer();
+    let mut store = TestAccount::new_account(&Store {
+        owner: signer.key,
+        bump: 254,
+        ..new_store()
+    });
+    let mut loyalty_program = TestAccount::new_account(&LoyaltyProgram {
+        store: Pubkey::default(),
+        reward_coupon: Pubkey::default(),
+        reward_mint: Pubkey::default(),
+        threshold: 0,
+        bump: 0,
+    });
+    let reward_mint = Pubkey::new_unique();
+    let mut reward_coupon = TestAccount::new_account(&Coupon {
+        mint: reward_mint,
+        store: store.key,
+        bump: 254,
+        ..new_coupon()
+    });
+    let mut system_program = TestAccount::new_program(System::id());
+
+    let mut accounts = SetLoyaltyProgram {
+        store: Account::try_from(&store.info()).unwrap(),
+        loyalty_program: Account::try_from(&loyalty_program.info()).unwrap(),
+        reward_coupon: Account::try_from(&reward_coupon.info()).unwrap(),
+        signer: Signer::try_from(&signer.info()).unwrap(),
+        payer: Signer::try_from(&payer.info()).unwrap(),
+        system_program: Program::try_from(&system_program.info()).unwrap(),
+    };
+
+    let err = christmas::set_loyalty_program(
+        Context::new(&crate::ID, &mut accounts, &[], bumps(&["loyalty_program"])),
+        0,
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::InvalidLoyaltyThreshold.into());
+
+    christmas::set_loyalty_program(
+        Context::new(&crate::ID, &mut accounts, &[], bumps(&["loyalty_program"])),
+        10,
+    )
+    .unwrap();
+    assert_eq!(accounts.loyalty_program.store, accounts.store.key());
+    assert_eq!(
+        accounts.loyalty_program.reward_coupon,
+        accounts.reward_coupon.key()
+    );
+    assert_eq!(accounts.loyalty_program.reward_mint, reward_mint);
+    assert_eq!(accounts.loyalty_program.threshold, 10);
+    assert_eq!(accounts.loyalty_program.bump, 254);
+}
+
+#[test]
+fn test_redeem_coupon_loyalty() {
+    let mut fixture = RedeemCouponFixture::new();
+    let mut reward_mint = TestAccount::new_mint(Pubkey::new_unique(), 0);
+    let mut reward_coupon = TestAccount::new_account(&Coupon {
+        mint: reward_mint.key,
+        store: fixture.store.key,
+        supply: 1,
+        bump: 254,
+        ..new_coupon()
+    });
+    let mut loyalty_program = TestAccount::new_account(&LoyaltyProgram {
+        store: fixture.store.key,
+        reward_coupon: reward_coupon.key,
+        reward_mint: reward_mint.key,
+        threshold: 2,
+        bump: 254,
+    });
+    let mut punch_card = TestAccount::new_account(&new_punch_card());
+    let mut other_token_account =
+        TestAccount::new_token_account(reward_mint.key, fixture.user.key, 0);
+    let mut reward_user_token_account =
+        TestAccount::new_token_account(reward_mint.key, fixture.user.key, 0).freeze_token_account();
+    reward_user_token_account.key = anchor_spl::associated_token::get_associated_token_address(
+        &fixture.user.key,
+        &reward_mint.key,
+    );
+    let mut associated_token_program = TestAccount::new_program(anchor_spl::associated_token::ID);
+
+    let mut accounts = fixture.accounts();
+    accounts.loyalty_program = Some(Account::try_from(&loyalty_program.info()).unwrap());
+    accounts.punch_card = Some(Account::try_from(&punch_card.info()).unwrap());
+    warp_to(1_705_000_000);
+
+    // redeeming nothing doesn't punch the card
+    let err = christmas::redeem_coupon(
+        Context::new(&crate::ID, &mut accounts, &[], RedeemCouponFixture::bumps()),
+        0,
+        0,
+        String::new(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::InvalidNumTokens.into());
+    assert_eq!(accounts.punch_card.as_ref().unwrap().punches, 0);
+
+    christmas::redeem_coupon(
+        Context::new(&crate::ID, &mut accounts, &[], RedeemCouponFixture::bumps()),
+        1,
+        0,
+        String::new(),
+    )
+    .unwrap();
+    assert_eq!(accounts.punch_card.as_ref().unwrap().punches, 1);
+    assert_eq!(accounts.punch_card.as_ref().unwrap().bump, 254);
+    take_cpis();
+
+    // full punch card needs the reward accounts
+    let err = christmas::redeem_coupon(
+        Context::new(&crate::ID, &mut accounts, &[], RedeemCouponFixture::bumps()),
+        1,
+        1,
+        String::new(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::InvalidLoyaltyAccounts.into());
+    take_cpis();
+
+    // reward must go to the user's associated token account
+    accounts.punch_card.as_mut().unwrap().punches = 1;
+    accounts.reward_coupon = Some(Account::try_from(&reward_coupon.info()).unwrap());
+    accounts.reward_mint = Some(Account::try_from(&reward_mint.info()).unwrap());
+    accounts.reward_user_token_account =
+        Some(UncheckedAccount::try_from(other_token_account.info()));
+    accounts.associated_token_program =
+        Some(Program::try_from(&associated_token_program.info()).unwrap());
+    let err = christmas::redeem_coupon(
+        Context::new(&crate::ID, &mut accounts, &[], RedeemCouponFixture::bumps()),
+        1,
+        2,
+        String::new(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::InvalidLoyaltyAccounts.into());
+    take_cpis();
+
+    // mints the reward coupon (creating the account if needed)
+    accounts.punch_card.as_mut().unwrap().punches = 1;
+    accounts.reward_user_token_account =
+        Some(UncheckedAccount::try_from(reward_user_token_account.info()));
+    christmas::redeem_coupon(
+        Context::new(&crate::ID, &mut accounts, &[], RedeemCouponFixture::bumps()),
+        1,
+        3,
+        String::new(),
+    )
+    .unwrap();
+    let cpis = take_cpis();
+    assert_eq!(cpis[1].program_id, anchor_spl::associated_token::ID);
+    assert_eq!(
+        token_instructions(&[cpis[0].clone(), cpis[2].clone()]),
+        vec![
+            TokenInstruction::Burn { amount: 1 },
+            TokenInstruction::MintTo { amount: 1 }
+        ]
+    );
+    assert_eq!(accounts.punch_card.as_ref().unwrap().punches, 0);
+    assert_eq!(accounts.reward_coupon.as_ref().unwrap().supply, 2);
+
+    // thaws and refreezes `soulbound` reward coupons
+    accounts.punch_card.as_mut().unwrap().punches = 1;
+    accounts.reward_coupon.as_mut().unwrap().options.soulbound = true;
+    christmas::redeem_coupon(
+        Context::new(&crate::ID, &mut accounts, &[], RedeemCouponFixture::bumps()),
+        1,
+        4,
+        String::new(),
+    )
+    .unwrap();
+    assert_eq!(
+        token_instructions(&take_cpis()[2..]),
+        vec![
+            TokenInstruction::ThawAccount,
+            TokenInstruction::MintTo { amount: 1 },
+            TokenInstruction::FreezeAccount
+        ]
+    );
+
+    // reward coupon max supply
+    accounts.punch_card.as_mut().unwrap().punches = 1;
+    accounts.reward_coupon.as_mut().unwrap().options.max_supply = 3;
+    let err = christmas::redeem_coupon(
+        Context::new(&crate::ID, &mut accounts, &[], RedeemCouponFixture::bumps()),
+        1,
+        5,
+        String::new(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::MaxSupplyExceeded.into());
+}
@@ -0,0 +1,673 @@
+use super::*;
+
+#[test]
+fn test_mint_to_market_max_supply() {
+    let mut fixture = MintToMarketFixture::new();
+    let mut accounts = fixture.accounts();
+    accounts.coupon.options.max_supply = 10;
+
+    christmas::mint_to_market(
+        Context::new(&crate::ID, &mut accounts, &[], MintToMarketFixture::bumps()),
+        *b"SGP",
+        6,
+    )
+    .unwrap();
+    assert_eq!(accounts.coupon.supply, 6);
+    assert!(accounts.coupon.has_supply);
+
+    let err = christmas::mint_to_market(
+        Context::new(&crate::ID, &mut accounts, &[], MintToMarketFixture::bumps()),
+        *b"SGP",
+        5,
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::MaxSupplyExceeded.into());
+    assert_eq!(accounts.coupon.supply, 6);
+
+    let err = christmas::mint_to_market(
+        Context::new(&crate::ID, &mut accounts, &[], MintToMarketFixture::bumps()),
+        *b"SGP",
+        u64::MAX,
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::SupplyOverflow.into());
+
+    accounts.config.max_mint_per_call = 3;
+    let err = christmas::mint_to_market(
+        Context::new(&crate::ID, &mut accounts, &[], MintToMarketFixture::bumps()),
+        *b"SGP",
+        4,
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::MintLimitExceeded.into());
+    accounts.config.max_mint_per_call = 0;
+
+    christmas::mint_to_market(
+        Context::new(&crate::ID, &mut accounts, &[], MintToMarketFixture::bumps()),
+        *b"SGP",
+        4,
+    )
+    .unwrap();
+    assert_eq!(accounts.coupon.supply, 10);
+}
+
+#[test]
+fn test_mint_to_market_delegate() {
+    let mut fixture = MintToMarketFixture::new();
+    let mut role = TestAccount::new_account(&StoreRole {
+        store: fixture.store.key,
+        delegate: fixture.signer.key,
+        permissions: PERMISSION_CREATE_COUPONS,
+        bump: 254,
+        ..new_store_role()
+    });
+
+    let mut accounts = fixture.accounts();
+    accounts.store.owner = Pubkey::new_unique();
+    accounts.coupon.update_authority = Pubkey::default();
+    warp_to(1_705_000_000);
+
+    // not the owner
+    let err = christmas::mint_to_market(
+        Context::new(&crate::ID, &mut accounts, &[], MintToMarketFixture::bumps()),
+        *b"SGP",
+        5,
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::Unauthorized.into());
+
+    // delegate without permission
+    accounts.role = Some(Account::try_from(&role.info()).unwrap());
+    let err = christmas::mint_to_market(
+        Context::new(&crate::ID, &mut accounts, &[], MintToMarketFixture::bumps()),
+        *b"SGP",
+        5,
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::Unauthorized.into());
+
+    accounts.role.as_mut().unwrap().permissions = PERMISSION_MINT_TO_MARKET;
+    christmas::mint_to_market(
+        Context::new(&crate::ID, &mut accounts, &[], MintToMarketFixture::bumps()),
+        *b"SGP",
+        5,
+    )
+    .unwrap();
+    assert_eq!(accounts.coupon.supply, 5);
+    let cpis = take_cpis();
+    assert_eq!(
+        token_instructions(&cpis),
+        vec![TokenInstruction::MintTo { amount: 5 }]
+    );
+    // signed by the coupon (mint authority)
+    assert!(cpis[0].accounts[2].is_signer);
+    assert_eq!(cpis[0].accounts[2].pubkey, accounts.coupon.key());
+}
+
+#[test]
+fn test_burn_from_market() {
+    let mut signer = TestAccount::new_signer();
+    let mut mint = TestAccount::new_mint(signer.key, 10);
+    let mut region_market = TestAccount::new_account(&RegionMarket {
+        region: *b"SGP",
+        bump: 254,
+    });
+    let mut region_market_token_account =
+        TestAccount::new_token_account(mint.key, region_market.key, 10);
+    let mut burnt_region_market_token_account =
+        TestAccount::new_token_account(mint.key, region_market.key, 6);
+    let mut coupon = TestAccount::new_account(&Coupon {
+        update_authority: signer.key,
+        mint: mint.key,
+        region: *b"SGP",
+        supply: 10,
+        has_supply: true,
+        bump: 254,
+        ..new_coupon()
+    });
+    let mut token_program = TestAccount::new_program(anchor_spl::token::ID);
+
+    let mut state = TestAccount::new_account(&new_state());
+    let mut store = TestAccount::new_account(&new_store());
+    let mut accounts = BurnFromMarket {
+        region_market_token_account: Account::try_from(&region_market_token_account.info())
+            .unwrap(),
+        region_market: Account::try_from(&region_market.info()).unwrap(),
+        mint: Account::try_from(&mint.info()).unwrap(),
+        coupon: Account::try_from(&coupon.info()).unwrap(),
+        store: Account::try_from(&store.info()).unwrap(),
+        state: Account::try_from(&state.info()).unwrap(),
+        signer: Signer::try_from(&signer.info()).unwrap(),
+        token_program: Program::try_from(&token_program.info()).unwrap(),
+    };
+
+    let err = christmas::burn_from_market(
+        Context::new(&crate::ID, &mut accounts, &[], BTreeMap::new()),
+        11,
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::InsufficientSupply.into());
+    assert_eq!(accounts.coupon.supply, 10);
+
+    christmas::burn_from_market(
+        Context::new(&crate::ID, &mut accounts, &[], BTreeMap::new()),
+        4,
+    )
+    .unwrap();
+    assert_eq!(accounts.coupon.supply, 6);
+    assert!(accounts.coupon.has_supply);
+
+    accounts.region_market_token_account =
+        Account::try_from(&burnt_region_market_token_account.info()).unwrap();
+    christmas::burn_from_market(
+        Context::new(&crate::ID, &mut accounts, &[], BTreeMap::new()),
+        6,
+    )
+    .unwrap();
+    assert_eq!(accounts.coupon.supply, 0);
+    assert!(!accounts.coupon.has_supply);
+}
+
+#[test]
+fn test_burn_from_market_after_claims() {
+    let mut signer = TestAccount::new_signer();
+    let mut mint = TestAccount::new_mint(signer.key, 10);
+    let mut region_market = TestAccount::new_account(&RegionMarket {
+        region: *b"SGP",
+        bump: 254,
+    });
+    // 6 of 10 tokens have been claimed
+    let mut region_market_token_account =
+        TestAccount::new_token_account(mint.key, region_market.key, 4);
+    let mut coupon = TestAccount::new_account(&Coupon {
+        update_authority: signer.key,
+        mint: mint.key,
+        region: *b"SGP",
+        supply: 10,
+        has_supply: true,
+        bump: 254,
+        ..new_coupon()
+    });
+    let mut token_program = TestAccount::new_program(anchor_spl::token::ID);
+
+    let mut state = TestAccount::new_account(&new_state());
+    let mut store = TestAccount::new_account(&new_store());
+    let mut accounts = BurnFromMarket {
+        region_market_token_account: Account::try_from(&region_market_token_account.info())
+            .unwrap(),
+        region_market: Account::try_from(&region_market.info()).unwrap(),
+        mint: Account::try_from(&mint.info()).unwrap(),
+        coupon: Account::try_from(&coupon.info()).unwrap(),
+        store: Account::try_from(&store.info()).unwrap(),
+        state: Account::try_from(&state.info()).unwrap(),
+        signer: Signer::try_from(&signer.info()).unwrap(),
+        token_program: Program::try_from(&token_program.info()).unwrap(),
+    };
+
+    // claimed tokens are not in the market
+    let err = christmas::burn_from_market(
+        Context::new(&crate::ID, &mut accounts, &[], BTreeMap::new()),
+        5,
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::InsufficientSupply.into());
+
+    // market is empty even though claimed tokens are still in circulation
+    christmas::burn_from_market(
+        Context::new(&crate::ID, &mut accounts, &[], BTreeMap::new()),
+        4,
+    )
+    .unwrap();
+    assert_eq!(accounts.coupon.supply, 6);
+    assert!(!accounts.coupon.has_supply);
+}
+
+#[test]
+fn test_mint_to_market_after_claims() {
+    let mut fixture = MintToMarketFixture::new();
+    let mut accounts = fixture.accounts();
+    // all 6 tokens have been claimed
+    accounts.coupon.supply = 6;
+
+    christmas::mint_to_market(
+        Context::new(&crate::ID, &mut accounts, &[], MintToMarketFixture::bumps()),
+        *b"SGP",
+        0,
+    )
+    .unwrap();
+    assert_eq!(accounts.coupon.supply, 6);
+    assert!(!accounts.coupon.has_supply);
+
+    christmas::mint_to_market(
+        Context::new(&crate::ID, &mut accounts, &[], MintToMarketFixture::bumps()),
+        *b"SGP",
+        2,
+    )
+    .unwrap();
+    assert_eq!(accounts.coupon.supply, 8);
+    assert!(accounts.coupon.has_supply);
+}
+
+#[test]
+fn test_claim_from_market_validity() {
+    let mut fixture = ClaimFromMarketFixture::new();
+    let mut accounts = fixture.accounts();
+
+    // region is disabled
+    warp_to(1_705_000_000);
+    accounts.config.set_region_enabled(b"SGP", false).unwrap();
+    let err = christmas::claim_from_market(
+        Context::new(
+            &crate::ID,
+            &mut accounts,
+            &[],
+            ClaimFromMarketFixture::bumps(),
+        ),
+        1,
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::RegionDisabled.into());
+    accounts.config.set_region_enabled(b"SGP", true).unwrap();
+
+    warp_to(1_704_067_199);
+    let err = christmas::claim_from_market(
+        Context::new(
+            &crate::ID,
+            &mut accounts,
+            &[],
+            ClaimFromMarketFixture::bumps(),
+        ),
+        1,
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::CouponNotYetValid.into());
+
+    warp_to(1_706_745_601);
+    let err = christmas::claim_from_market(
+        Context::new(
+            &crate::ID,
+            &mut accounts,
+            &[],
+            ClaimFromMarketFixture::bumps(),
+        ),
+        1,
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::CouponExpired.into());
+
+    for timestamp in [1_704_067_200, 1_705_000_000, 1_706_745_600] {
+        warp_to(timestamp);
+        christmas::claim_from_market(
+            Context::new(
+                &crate::ID,
+                &mut accounts,
+                &[],
+                ClaimFromMarketFixture::bumps(),
+            ),
+            1,
+        )
+        .unwrap();
+    }
+}
+
+#[test]
+fn test_claim_from_market_limit() {
+    let mut fixture = ClaimFromMarketFixture::new();
+    let mut accounts = fixture.accounts();
+    accounts.coupon.options.max_claims_per_user = 2;
+    warp_to(1_705_000_000);
+
+    // can't claim more than the limit at once
+    let err = christmas::claim_from_market(
+        Context::new(
+            &crate::ID,
+            &mut accounts,
+            &[],
+            ClaimFromMarketFixture::bumps(),
+        ),
+        3,
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::ClaimLimitExceeded.into());
+    assert_eq!(accounts.claim_record.claimed, 0);
+
+    christmas::claim_from_market(
+        Context::new(
+            &crate::ID,
+            &mut accounts,
+            &[],
+            ClaimFromMarketFixture::bumps(),
+        ),
+        1,
+    )
+    .unwrap();
+    christmas::claim_from_market(
+        Context::new(
+            &crate::ID,
+            &mut accounts,
+            &[],
+            ClaimFromMarketFixture::bumps(),
+        ),
+        1,
+    )
+    .unwrap();
+    assert_eq!(accounts.claim_record.claimed, 2);
+    assert_eq!(accounts.claim_record.bump, 254);
+
+    // can't claim more than the limit over several claims
+    let err = christmas::claim_from_market(
+        Context::new(
+            &crate::ID,
+            &mut accounts,
+            &[],
+            ClaimFromMarketFixture::bumps(),
+        ),
+        1,
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::ClaimLimitExceeded.into());
+    assert_eq!(accounts.claim_record.claimed, 2);
+}
+
+#[test]
+fn test_claim_from_market_supply() {
+    let mut fixture = ClaimFromMarketFixture::new();
+    // market after the first claim
+    let mut claimed_region_market_token_account =
+        TestAccount::new_token_account(fixture.mint.key, fixture.region_market.key, 6);
+    let mut accounts = fixture.accounts();
+    accounts.coupon.supply = 10;
+    accounts.coupon.has_supply = true;
+    warp_to(1_705_000_000);
+
+    // can't claim more than the market holds
+    let err = christmas::claim_from_market(
+        Context::new(
+            &crate::ID,
+            &mut accounts,
+            &[],
+            ClaimFromMarketFixture::bumps(),
+        ),
+        11,
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::InsufficientSupply.into());
+
+    christmas::claim_from_market(
+        Context::new(
+            &crate::ID,
+            &mut accounts,
+            &[],
+            ClaimFromMarketFixture::bumps(),
+        ),
+        4,
+    )
+    .unwrap();
+    assert!(accounts.coupon.has_supply);
+
+    // claiming the rest empties the market
+    accounts.region_market_token_account =
+        Account::try_from(&claimed_region_market_token_account.info()).unwrap();
+    christmas::claim_from_market(
+        Context::new(
+            &crate::ID,
+            &mut accounts,
+            &[],
+            ClaimFromMarketFixture::bumps(),
+        ),
+        6,
+    )
+    .unwrap();
+    assert!(!accounts.coupon.has_supply);
+    assert_eq!(accounts.coupon.supply, 10);
+}
+
+#[test]
+fn test_claim_from_market_soulbound() {
+    let mut fixture = ClaimFromMarketFixture::new();
+    let mut frozen_user_token_account =
+        TestAccount::new_token_account(fixture.mint.key, fixture.user.key, 1)
+            .freeze_token_account();
+
+    let mut accounts = fixture.accounts();
+    accounts.coupon.options.soulbound = true;
+    warp_to(1_705_000_000);
+
+    christmas::claim_from_market(
+        Context::new(
+            &crate::ID,
+            &mut accounts,
+            &[],
+            ClaimFromMarketFixture::bumps(),
+        ),
+        1,
+    )
+    .unwrap();
+    assert_eq!(
+        token_instructions(&take_cpis()),
+        vec![
+            TokenInstruction::Transfer { amount: 1 },
+            TokenInstruction::FreezeAccount
+        ]
+    );
+
+    // already frozen from the previous claim
+    accounts.user_token_account = Account::try_from(&frozen_user_token_account.info()).unwrap();
+    christmas::claim_from_market(
+        Context::new(
+            &crate::ID,
+            &mut accounts,
+            &[],
+            ClaimFromMarketFixture::bumps(),
+        ),
+        1,
+    )
+    .unwrap();
+    assert_eq!(
+        token_instructions(&take_cpis()),
+        vec![
+            TokenInstruction::ThawAccount,
+            TokenInstruction::Transfer { amount: 1 },
+            TokenInstruction::FreezeAccount
+        ]
+    );
+}
+
+#[test]
+fn test_claim_from_market_paid() {
+    let mut fixture = ClaimFromMarketFixture::new();
+    let store = Pubkey::new_unique();
+    let usdc = Pubkey::new_unique();
+    let mut treasury = TestAccount::new_account(&new_treasury());
+    let mut signer_token_account = TestAccount::new_token_account(usdc, fixture.signer.key, 1_000);
+    let mut treasury_token_account = TestAccount::new_token_account(usdc, treasury.key, 0);
+    let mut other_token_account = TestAccount::new_token_account(usdc, fixture.signer.key, 0);
+    let mut fee_recipient = TestAccount::new_signer();
+    let mut fee_token_account = TestAccount::new_token_account(usdc, fee_recipient.key, 0);
+    let mut points_mint = TestAccount::new_mint(Pubkey::new_unique(), 0);
+    let mut store_points = TestAccount::new_account(&StorePoints {
+        store,
+        mint: points_mint.key,
+        claim_rate: 3,
+        bump: 254,
+        ..new_store_points()
+    });
+    let mut user_points_token_account =
+        TestAccount::new_token_account(points_mint.key, fixture.user.key, 0);
+
+    let mut accounts = fixture.accounts();
+    accounts.coupon.store = store;
+    accounts.coupon.options.price = 100;
+    warp_to(1_705_000_000);
+
+    // requires the treasury
+    let err = christmas::claim_from_market(
+        Context::new(
+            &crate::ID,
+            &mut accounts,
+            &[],
+            ClaimFromMarketFixture::bumps(),
+        ),
+        2,
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::InvalidPaymentAccounts.into());
+
+    // priced in lamports
+    accounts.treasury = Some(Account::try_from(&treasury.info()).unwrap());
+    christmas::claim_from_market(
+        Context::new(
+            &crate::ID,
+            &mut accounts,
+            &[],
+            ClaimFromMarketFixture::bumps(),
+        ),
+        2,
+    )
+    .unwrap();
+    assert_eq!(accounts.treasury.as_ref().unwrap().store, store);
+    assert_eq!(accounts.treasury.as_ref().unwrap().bump, 254);
+    let cpis = take_cpis();
+    assert_eq!(
+        cpis[0],
+        solana_program::system_instruction::transfer(
+            &accounts.signer.key(),
+            &accounts.treasury.as_ref().unwrap().key(),
+            200
+        )
+    );
+    assert_eq!(
+        token_instructions(&cpis[1..]),
+        vec![TokenInstruction::Transfer { amount: 2 }]
+    );
+
+    // priced in an SPL token
+    accounts.coupon.options.price_mint = usdc;
+    let err = christmas::claim_from_market(
+        Context::new(
+            &crate::ID,
+            &mut accounts,
+            &[],
+            ClaimFromMarketFixture::bumps(),
+        ),
+        2,
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::InvalidPaymentAccounts.into());
+
+    accounts.signer_token_account = Some(Account::try_from(&signer_token_account.info()).unwrap());
+    accounts.treasury_token_account = Some(Account::try_from(&other_token_account.info()).unwrap());
+    let err = christmas::claim_from_market(
+        Context::new(
+            &crate::ID,
+            &mut accounts,
+            &[],
+            ClaimFromMarketFixture::bumps(),
+        ),
+        2,
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::InvalidPaymentAccounts.into());
+
+    accounts.treasury_token_account =
+        Some(Account::try_from(&treasury_token_account.info()).unwrap());
+    christmas::claim_from_market(
+        Context::new(
+            &crate::ID,
+            &mut accounts,
+            &[],
+            ClaimFromMarketFixture::bumps(),
+        ),
+        2,
+    )
+    .unwrap();
+    assert_eq!(
+        token_instructions(&take_cpis()),
+        vec![
+            TokenInstruction::Transfer { amount: 200 },
+            TokenInstruction::Transfer { amount: 2 }
+        ]
+    );
+
+    // earns points for paid claims
+    accounts.store_points = Some(Account::try_from(&store_points.info()).unwrap());
+    accounts.points_mint = Some(Account::try_from(&points_mint.info()).unwrap());
+    accounts.user_points_token_account =
+        Some(Account::try_from(&user_points_token_account.info()).unwrap());
+    christmas::claim_from_market(
+        Context::new(
+            &crate::ID,
+            &mut accounts,
+            &[],
+            ClaimFromMarketFixture::bumps(),
+        ),
+        2,
+    )
+    .unwrap();
+    assert_eq!(
+        token_instructions(&take_cpis()),
+        vec![
+            TokenInstruction::Transfer { amount: 200 },
+            TokenInstruction::MintTo { amount: 6 },
+            TokenInstruction::Transfer { amount: 2 }
+        ]
+    );
+
+    // protocol fee is taken out of the price
+    accounts.store_points = None;
+    accounts.config.fee_rate = 500; // 5%
+    accounts.config.fee_recipient = fee_recipient.key;
+    let err = christmas::claim_from_market(
+        Context::new(
+            &crate::ID,
+            &mut accounts,
+            &[],
+            ClaimFromMarketFixture::bumps(),
+        ),
+        2,
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::InvalidPaymentAccounts.into());
+
+    accounts.fee_token_account = Some(Account::try_from(&fee_token_account.info()).unwrap());
+    christmas::claim_from_market(
+        Context::new(
+            &crate::ID,
+            &mut accounts,
+            &[],
+            ClaimFromMarketFixture::bumps(),
+        ),
+        2,
+    )
+    .unwrap();
+    assert_eq!(
+        token_instructions(&take_cpis()),
+        vec![
+            TokenInstruction::Transfer { amount: 190 },
+            TokenInstruction::Transfer { amount: 10 },
+            TokenInstruction::Transfer { amount: 2 }
+        ]
+    );
+
+    accounts.coupon.options.price_mint = Pubkey::default();
+    accounts.fee_recipient = Some(UncheckedAccount::try_from(fee_recipient.info()));
+    christmas::claim_from_market(
+        Context::new(
+            &crate::ID,
+            &mut accounts,
+            &[],
+            ClaimFromMarketFixture::bumps(),
+        ),
+        2,
+    )
+    .unwrap();
+    let cpis = take_cpis();
+    assert_eq!(
+        cpis[1],
+        solana_program::system_instruction::transfer(
+            &accounts.signer.key(),
+            &fee_recipient.key,
+            10
+        )
+    );
+}
@@ -0,0 +1,519 @@
+use super::*;
+use crate::defs::{
+    COUPON_NAME_SIZE, DISCRIMINATOR_SIZE, MAX_FEE_RATE, REGION_BITMAP_SIZE, STORE_NAME_SIZE,
+    STRING_PREFIX_SIZE, URI_SIZE,
+};
+use crate::errors::ChristmasError;
+use crate::test_utils::{take_cpis, warp_to, TestAccount};
+use anchor_lang::error::ErrorCode;
+use anchor_spl::token::spl_token::instruction::TokenInstruction;
+use solana_program::instruction::Instruction;
+use std::collections::{BTreeMap, BTreeSet};
+
+mod config;
+mod coupon;
+mod loyalty;
+mod market;
+mod points;
+mod redemption;
+mod role;
+mod state;
+mod store;
+mod treasury;
+mod user;
+
+fn new_user() -> User {
+    User {
+        region: [0; 3],
+        uri: String::new(),
+        bump: 0,
+    }
+}
+
+fn new_store() -> Store {
+    Store {
+        id: 0,
+        name: String::new(),
+        region: [0; 3],
+        geohash: [0; 6],
+        uri: String::new(),
+        owner: Pubkey::default(),
+        live_coupons: 0,
+        pending_owner: Pubkey::default(),
+        role_epoch: 0,
+        bump: 0,
+    }
+}
+
+fn new_coupon() -> Coupon {
+    Coupon {
+        update_authority: Pubkey::default(),
+        mint: Pubkey::default(),
+        name: String::new(),
+        uri: String::new(),
+        store: Pubkey::default(),
+        valid_from: 0,
+        valid_to: 0,
+        supply: 0,
+        region: [0; 3],
+        geohash: [0; 6],
+        has_supply: false,
+        valid_from_hash: [0; 32],
+        valid_to_hash: [0; 32],
+        datehash_overflow: false,
+        options: CouponOptions::default(),
+        terms: CouponTerms::default(),
+        bump: 0,
+    }
+}
+
+fn new_redemption() -> Redemption {
+    Redemption {
+        id: 0,
+        coupon: Pubkey::default(),
+        store: Pubkey::default(),
+        user: Pubkey::default(),
+        num_tokens: 0,
+        timestamp: 0,
+        reference: String::new(),
+        payer: Pubkey::default(),
+        bump: 0,
+    }
+}
+
+fn new_coupon_uses() -> CouponUses {
+    CouponUses {
+        remaining: 0,
+        bump: 0,
+    }
+}
+
+fn new_punch_card() -> PunchCard {
+    PunchCard {
+        punches: 0,
+        bump: 0,
+    }
+}
+
+fn new_store_points() -> StorePoints {
+    StorePoints {
+        store: Pubkey::default(),
+        mint: Pubkey::default(),
+        redeem_rate: 0,
+        claim_rate: 0,
+        reward_coupon: Pubkey::default(),
+        reward_cost: 0,
+        bump: 0,
+    }
+}
+
+fn new_store_role() -> StoreRole {
+    StoreRole {
+        store: Pubkey::default(),
+        delegate: Pubkey::default(),
+        permissions: 0,
+        epoch: 0,
+        bump: 0,
+    }
+}
+
+fn new_claim_record() -> ClaimRecord {
+    ClaimRecord {
+        claimed: 0,
+        bump: 0,
+    }
+}
+
+fn new_treasury() -> Treasury {
+    Treasury {
+        store: Pubkey::default(),
+        bump: 0,
+    }
+}
+
+fn new_region_market() -> RegionMarket {
+    RegionMarket {
+        region: [0; 3],
+        bump: 0,
+    }
+}
+
+fn new_state() -> ProgramState {
+    ProgramState {
+        is_initialized: true,
+        store_counter: 0,
+        active_stores: 0,
+        admin: Pubkey::default(),
+        paused: false,
+        bump: 255,
+    }
+}
+
+fn new_config() -> Config {
+    Config {
+        max_mint_per_call: 0,
+        max_coupons_per_store: 0,
+        fee_rate: 0,
+        fee_recipient: Pubkey::default(),
+        disabled_regions: [0; REGION_BITMAP_SIZE],
+        bump: 255,
+    }
+}
+
+fn token_instructions(cpis: &[Instruction]) -> Vec<TokenInstruction<'_>> {
+    cpis.iter()
+        .map(|ix| TokenInstruction::unpack(&ix.data).unwrap())
+        .collect()
+}
+
+fn bumps(names: &[&str]) -> BTreeMap<String, u8> {
+    names.iter().map(|name| (name.to_string(), 254)).collect()
+}
+
+fn find_pda(seeds: &[&[u8]]) -> (Pubkey, u8) {
+    Pubkey::find_program_address(seeds, &crate::ID)
+}
+
+// Checks the `#[account(...)]` constraints of `T`, which handlers called with `Context::new` skip
+fn try_accounts<'info, T: Accounts<'info>>(infos: &[AccountInfo<'info>]) -> Result<T> {
+    T::try_accounts(
+        &crate::ID,
+        &mut &infos[..],
+        &[],
+        &mut BTreeMap::new(),
+        &mut BTreeSet::new(),
+    )
+}
+
+/*
+    Account contexts shared by several tests. `new` sets up the happy path (a coupon in SGP valid
+    for january 2024), tests tweak the deserialized accounts through `accounts()` and attach any
+    optional accounts they need.
+*/
+struct ClaimFromMarketFixture {
+    payer: TestAccount,
+    signer: TestAccount,
+    mint: TestAccount,
+    user: TestAccount,
+    user_token_account: TestAccount,
+    region_market: TestAccount,
+    region_market_token_account: TestAccount,
+    coupon: TestAccount,
+    claim_record: TestAccount,
+    state: TestAccount,
+    config: TestAccount,
+    associated_token_program: TestAccount,
+    token_program: TestAccount,
+    system_program: TestAccount,
+}
+
+impl ClaimFromMarketFixture {
+    fn new() -> Self {
+        let mint = TestAccount::new_mint(Pubkey::new_unique(), 10);
+        let user = TestAccount::new_account(&new_user());
+        let region_market = TestAccount::new_account(&RegionMarket {
+            region: *b"SGP",
+            bump: 254,
+        });
+        ClaimFromMarketFixture {
+            payer: TestAccount::new_signer(),
+            signer: TestAccount::new_signer(),
+            user_token_account: TestAccount::new_token_account(mint.key, user.key, 0),
+            region_market_token_account: TestAccount::new_token_account(
+                mint.key,
+                region_market.key,
+                10,
+            ),
+            coupon: TestAccount::new_account(&Coupon {
+                mint: mint.key,
+                region: *b"SGP",
+                valid_from: 1_704_067_200_000, // 1 jan 2024
+                valid_to: 1_706_745_600_000,   // 1 feb 2024
+                bump: 254,
+                ..new_coupon()
+            }),
+            claim_record: TestAccount::new_account(&new_claim_record()),
+            state: TestAccount::new_account(&new_state()),
+            config: TestAccount::new_account(&new_config()),
+            associated_token_program: TestAccount::new_program(anchor_spl::associated_token::ID),
+            token_program: TestAccount::new_program(anchor_spl::token::ID),
+            system_program: TestAccount::new_program(System::id()),
+            mint,
+            user,
+            region_market,
+        }
+    }
+
+    fn accounts(&mut self) -> ClaimFromMarket<'_> {
+        ClaimFromMarket {
+            user: Account::try_from(&self.user.info()).unwrap(),
+            user_token_account: Account::try_from(&self.user_token_account.info()).unwrap(),
+            region_market_token_account: Account::try_from(
+                &self.region_market_token_account.info(),
+            )
+            .unwrap(),
+            region_market: Account::try_from(&self.region_market.info()).unwrap(),
+            mint: Account::try_from(&self.mint.info()).unwrap(),
+            coupon: Account::try_from(&self.coupon.info()).unwrap(),
+            claim_record: Account::try_from(&self.claim_record.info()).unwrap(),
+            treasury: None,
+            signer_token_account: None,
+            treasury_token_account: None,
+            store_points: None,
+            points_mint: None,
+            user_points_token_account: None,
+            payer: Signer::try_from(&self.payer.info()).unwrap(),
+            state: Account::try_from(&self.state.info()).unwrap(),
+            config: Account::try_from(&self.config.info()).unwrap(),
+            fee_recipient: None,
+            fee_token_account: None,
+            signer: Signer::try_from(&self.signer.info()).unwrap(),
+            associated_token_program: Program::try_from(&self.associated_token_program.info())
+                .unwrap(),
+            token_program: Program::try_from(&self.token_program.info()).unwrap(),
+            system_program: Program::try_from(&self.system_program.info()).unwrap(),
+        }
+    }
+
+    fn bumps() -> BTreeMap<String, u8> {
+        bumps(&["user", "claim_record", "treasury"])
+    }
+}
+
+struct RedeemCouponFixture {
+    payer: TestAccount,
+    signer: TestAccount,
+    mint: TestAccount,
+    user: TestAccount,
+    user_token_account: TestAccount,
+    store: TestAccount,
+    coupon: TestAccount,
+    redemption: TestAccount,
+    state: TestAccount,
+    config: TestAccount,
+    token_program: TestAccount,
+    system_program: TestAccount,
+}
+
+impl RedeemCouponFixture {
+    fn new() -> Self {
+        let mint = TestAccount::new_mint(Pubkey::new_unique(), 10);
+        let user = TestAccount::new_account(&User {
+            region: *b"SGP",
+            bump: 254,
+            ..new_user()
+        });
+        let store = TestAccount::new_account(&Store {
+            bump: 254,
+            ..new_store()
+        });
+        RedeemCouponFixture {
+            payer: TestAccount::new_signer(),
+            signer: TestAccount::new_signer(),
+            user_token_account: TestAccount::new_token_account(mint.key, user.key, 10),
+            coupon: TestAccount::new_account(&Coupon {
+                mint: mint.key,
+                store: store.key,
+                region: *b"SGP",
+                valid_from: 1_704_067_200_000, // 1 jan 2024
+                valid_to: 1_706_745_600_000,   // 1 feb 2024
+                bump: 254,
+                ..new_coupon()
+            }),
+            redemption: TestAccount::new_account(&new_redemption()),
+            state: TestAccount::new_account(&new_state()),
+            config: TestAccount::new_account(&new_config()),
+            token_program: TestAccount::new_program(anchor_spl::token::ID),
+            system_program: TestAccount::new_program(System::id()),
+            mint,
+            user,
+            store,
+        }
+    }
+
+    fn accounts(&mut self) -> RedeemCoupon<'_> {
+        RedeemCoupon {
+            coupon: Account::try_from(&self.coupon.info()).unwrap(),
+            mint: Account::try_from(&self.mint.info()).unwrap(),
+            user: Account::try_from(&self.user.info()).unwrap(),
+            user_token_account: Account::try_from(&self.user_token_account.info()).unwrap(),
+            store: Account::try_from(&self.store.info()).unwrap(),
+            store_signer: None,
+            store_signer_role: None,
+            redemption: Account::try_from(&self.redemption.info()).unwrap(),
+            uses: None,
+            loyalty_program: None,
+            punch_card: None,
+            reward_coupon: None,
+            reward_mint: None,
+            reward_user_token_account: None,
+            associated_token_program: None,
+            store_points: None,
+            points_mint: None,
+            user_points_token_account: None,
+            state: Account::try_from(&self.state.info()).unwrap(),
+            config: Account::try_from(&self.config.info()).unwrap(),
+            signer: Signer::try_from(&self.signer.info()).unwrap(),
+            payer: Signer::try_from(&self.payer.info()).unwrap(),
+            token_program: Program::try_from(&self.token_program.info()).unwrap(),
+            system_program: Program::try_from(&self.system_program.info()).unwrap(),
+        }
+    }
+
+    fn bumps() -> BTreeMap<String, u8> {
+        bumps(&["redemption", "uses", "punch_card"])
+    }
+}
+
+struct MintToMarketFixture {
+    signer: TestAccount,
+    payer: TestAccount,
+    mint: TestAccount,
+    region_market: TestAccount,
+    region_market_token_account: TestAccount,
+    store: TestAccount,
+    coupon: TestAccount,
+    state: TestAccount,
+    config: TestAccount,
+    system_program: TestAccount,
+    associated_token_program: TestAccount,
+    token_program: TestAccount,
+}
+
+impl MintToMarketFixture {
+    fn new() -> Self {
+        let signer = TestAccount::new_signer();
+        let mint = TestAccount::new_mint(signer.key, 0);
+        let region_market = TestAccount::new_account(&RegionMarket {
+            region: *b"SGP",
+            bump: 254,
+        });
+        let store = TestAccount::new_account(&Store {
+            owner: signer.key,
+            bump: 254,
+            ..new_store()
+        });
+        MintToMarketFixture {
+            payer: TestAccount::new_signer(),
+            region_market_token_account: TestAccount::new_token_account(
+                mint.key,
+                region_market.key,
+                0,
+            ),
+            coupon: TestAccount::new_account(&Coupon {
+                update_authority: signer.key,
+                mint: mint.key,
+                store: store.key,
+                region: *b"SGP",
+                bump: 254,
+                ..new_coupon()
+            }),
+            state: TestAccount::new_account(&new_state()),
+            config: TestAccount::new_account(&new_config()),
+            system_program: TestAccount::new_program(System::id()),
+            associated_token_program: TestAccount::new_program(anchor_spl::associated_token::ID),
+            token_program: TestAccount::new_program(anchor_spl::token::ID),
+            signer,
+            mint,
+            region_market,
+            store,
+        }
+    }
+
+    fn accounts(&mut self) -> MintToMarket<'_> {
+        MintToMarket {
+            region_market: Account::try_from(&self.region_market.info()).unwrap(),
+            region_market_token_account: Account::try_from(
+                &self.region_market_token_account.info(),
+            )
+            .unwrap(),
+            mint: Account::try_from(&self.mint.info()).unwrap(),
+            coupon: Account::try_from(&self.coupon.info()).unwrap(),
+            store: Account::try_from(&self.store.info()).unwrap(),
+            role: None,
+            state: Account::try_from(&self.state.info()).unwrap(),
+            config: Account::try_from(&self.config.info()).unwrap(),
+            signer: Signer::try_from(&self.signer.info()).unwrap(),
+            payer: Signer::try_from(&self.payer.info()).unwrap(),
+            system_program: Program::try_from(&self.system_program.info()).unwrap(),
+            associated_token_program: Program::try_from(&self.associated_token_program.info())
+                .unwrap(),
+            token_program: Program::try_from(&self.token_program.info()).unwrap(),
+        }
+    }
+
+    fn bumps() -> BTreeMap<String, u8> {
+        bumps(&["region_market"])
+    }
+}
+
+struct CreateCouponFixture {
+    signer: TestAccount,
+    payer: TestAccount,
+    mint: TestAccount,
+    coupon: TestAccount,
+    region_market: TestAccount,
+    region_market_token_account: TestAccount,
+    store: TestAccount,
+    state: TestAccount,
+    config: TestAccount,
+    system_program: TestAccount,
+    token_program: TestAccount,
+    associated_token_program: TestAccount,
+}
+
+impl CreateCouponFixture {
+    fn new() -> Self {
+        let signer = TestAccount::new_signer();
+        let mint = TestAccount::new_mint(signer.key, 0);
+        let region_market = TestAccount::new_account(&new_region_market());
+        CreateCouponFixture {
+            payer: TestAccount::new_signer(),
+            coupon: TestAccount::new_account(&new_coupon()),
+            region_market_token_account: TestAccount::new_token_account(
+                mint.key,
+                region_market.key,
+                0,
+            ),
+            store: TestAccount::new_account(&Store {
+                owner: signer.key,
+                bump: 254,
+                ..new_store()
+            }),
+            state: TestAccount::new_account(&new_state()),
+            config: TestAccount::new_account(&new_config()),
+            system_program: TestAccount::new_program(System::id()),
+            token_program: TestAccount::new_program(anchor_spl::token::ID),
+            associated_token_program: TestAccount::new_program(anchor_spl::associated_token::ID),
+            signer,
+            mint,
+            region_market,
+        }
+    }
+
+    fn accounts(&mut self) -> CreateCoupon<'_> {
+        CreateCoupon {
+            mint: Account::try_from(&self.mint.info()).unwrap(),
+            coupon: Account::try_from(&self.coupon.info()).unwrap(),
+            region_market: Account::try_from(&self.region_market.info()).unwrap(),
+            region_market_token_account: Account::try_from(
+                &self.region_market_token_account.info(),
+            )
+            .unwrap(),
+            store: Account::try_from(&self.store.info()).unwrap(),
+            role: None,
+            state: Account::try_from(&self.state.info()).unwrap(),
+            config: Account::try_from(&self.config.info()).unwrap(),
+            signer: Signer::try_from(&self.signer.info()).unwrap(),
+            payer: Signer::try_from(&self.payer.info()).unwrap(),
+            system_program: Program::try_from(&self.system_program.info()).unwrap(),
+            token_program: Program::try_from(&self.token_program.info()).unwrap(),
+            associated_token_program: Program::try_from(&self.associated_token_program.info())
+                .unwrap(),
+        }
+    }
+
+    fn bumps() -> BTreeMap<String, u8> {
+        bumps(&["coupon", "region_market"])
+    }
+}
@@ -0,0 +1,252 @@
+use super::*;
+
+#[test]
+fn test_set_store_points() {
+    let mut signer = TestAccount::new_signer();
+    let mut payer = TestAccount::new_signer();
+    let mut store = TestAccount::new_account(&Store {
+        owner: signer.key,
+        bump: 254,
+        ..new_store()
+    });
+    let mut store_points = TestAccount::new_account(&new_store_points());
+    let mut points_mint = TestAccount::new_mint(store_points.key, 0);
+    let mut reward_coupon = TestAccount::new_account(&Coupon {
+        store: store.key,
+        bump: 254,
+        ..new_coupon()
+    });
+    let mut token_program = TestAccount::new_program(anchor_spl::token::ID);
+    let mut system_program = TestAccount::new_program(System::id());
+
+    let mut accounts = SetStorePoints {
+        store: Account::try_from(&store.info()).unwrap(),
+        store_points: Account::try_from(&store_points.info()).unwrap(),
+        points_mint: Account::try_from(&points_mint.info()).unwrap(),
+        reward_coupon: Some(Account::try_from(&reward_coupon.info()).unwrap()),
+        signer: Signer::try_from(&signer.info()).unwrap(),
+        payer: Signer::try_from(&payer.info()).unwrap(),
+        token_program: Program::try_from(&token_program.info()).unwrap(),
+        system_program: Program::try_from(&system_program.info()).unwrap(),
+    };
+
+    christmas::set_store_points(
+        Context::new(&crate::ID, &mut accounts, &[], bumps(&["store_points"])),
+        10,
+        5,
+        100,
+    )
+    .unwrap();
+    assert_eq!(accounts.store_points.store, accounts.store.key());
+    assert_eq!(accounts.store_points.mint, accounts.points_mint.key());
+    assert_eq!(accounts.store_points.redeem_rate, 10);
+    assert_eq!(accounts.store_points.claim_rate, 5);
+    assert_eq!(
+        accounts.store_points.reward_coupon,
+        accounts.reward_coupon.as_ref().unwrap().key()
+    );
+    assert_eq!(accounts.store_points.reward_cost, 100);
+    assert_eq!(accounts.store_points.bump, 254);
+
+    // remove the reward
+    accounts.reward_coupon = None;
+    christmas::set_store_points(
+        Context::new(&crate::ID, &mut accounts, &[], bumps(&["store_points"])),
+        10,
+        5,
+        0,
+    )
+    .unwrap();
+    assert_eq!(accounts.store_points.reward_coupon, Pubkey::default());
+    assert_eq!(accounts.store_points.reward_cost, 0);
+}
+
+#[test]
+fn test_redeem_coupon_points() {
+    let mut fixture = RedeemCouponFixture::new();
+    let mut points_mint = TestAccount::new_mint(Pubkey::new_unique(), 0);
+    let mut store_points = TestAccount::new_account(&StorePoints {
+        store: fixture.store.key,
+        mint: points_mint.key,
+        redeem_rate: 10,
+        bump: 254,
+        ..new_store_points()
+    });
+    let mut user_points_token_account =
+        TestAccount::new_token_account(points_mint.key, fixture.user.key, 0);
+
+    let mut accounts = fixture.accounts();
+    accounts.store_points = Some(Account::try_from(&store_points.info()).unwrap());
+    warp_to(1_705_000_000);
+
+    let err = christmas::redeem_coupon(
+        Context::new(&crate::ID, &mut accounts, &[], RedeemCouponFixture::bumps()),
+        2,
+        0,
+        String::new(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::InvalidPointsAccounts.into());
+    take_cpis();
+
+    accounts.points_mint = Some(Account::try_from(&points_mint.info()).unwrap());
+    accounts.user_points_token_account =
+        Some(Account::try_from(&user_points_token_account.info()).unwrap());
+    christmas::redeem_coupon(
+        Context::new(&crate::ID, &mut accounts, &[], RedeemCouponFixture::bumps()),
+        2,
+        0,
+        String::new(),
+    )
+    .unwrap();
+    assert_eq!(
+        token_instructions(&take_cpis()),
+        vec![
+            TokenInstruction::Burn { amount: 2 },
+            TokenInstruction::MintTo { amount: 20 }
+        ]
+    );
+}
+
+#[test]
+fn test_spend_points() {
+    let mut payer = TestAccount::new_signer();
+    let mut signer = TestAccount::new_signer();
+    let mut points_mint = TestAccount::new_mint(Pubkey::new_unique(), 100);
+    let mut reward_mint = TestAccount::new_mint(Pubkey::new_unique(), 10);
+    let mut store_points = TestAccount::new_account(&StorePoints {
+        mint: points_mint.key,
+        bump: 254,
+        ..new_store_points()
+    });
+    let mut user = TestAccount::new_account(&User {
+        region: *b"SGP",
+        bump: 254,
+        ..new_user()
+    });
+    let mut user_points_token_account =
+        TestAccount::new_token_account(points_mint.key, user.key, 100);
+    let mut reward_coupon = TestAccount::new_account(&Coupon {
+        mint: reward_mint.key,
+        region: *b"SGP",
+        valid_from: 1_704_067_200_000, // 1 jan 2024
+        valid_to: 1_706_745_600_000,   // 1 feb 2024
+        bump: 254,
+        ..new_coupon()
+    });
+    let mut region_market = TestAccount::new_account(&RegionMarket {
+        region: *b"SGP",
+        bump: 254,
+    });
+    let mut region_market_token_account =
+        TestAccount::new_token_account(reward_mint.key, region_market.key, 10);
+    let mut user_token_account = TestAccount::new_token_account(reward_mint.key, user.key, 0);
+    let mut frozen_user_token_account =
+        TestAccount::new_token_account(reward_mint.key, user.key, 2).freeze_token_account();
+    let mut associated_token_program = TestAccount::new_program(anchor_spl::associated_token::ID);
+    let mut token_program = TestAccount::new_program(anchor_spl::token::ID);
+    let mut system_program = TestAccount::new_program(System::id());
+
+    let mut claim_record = TestAccount::new_account(&new_claim_record());
+    let mut state = TestAccount::new_account(&new_state());
+    let mut config = TestAccount::new_account(&new_config());
+    let mut accounts = SpendPoints {
+        store_points: Account::try_from(&store_points.info()).unwrap(),
+        points_mint: Account::try_from(&points_mint.info()).unwrap(),
+        user: Account::try_from(&user.info()).unwrap(),
+        user_points_token_account: Account::try_from(&user_points_token_account.info()).unwrap(),
+        reward_coupon: Account::try_from(&reward_coupon.info()).unwrap(),
+        reward_mint: Account::try_from(&reward_mint.info()).unwrap(),
+        region_market: Account::try_from(&region_market.info()).unwrap(),
+        region_market_token_account: Account::try_from(&region_market_token_account.info())
+            .unwrap(),
+        user_token_account: Account::try_from(&user_token_account.info()).unwrap(),
+        claim_record: Account::try_from(&claim_record.info()).unwrap(),
+        state: Account::try_from(&state.info()).unwrap(),
+        config: Account::try_from(&config.info()).unwrap(),
+        signer: Signer::try_from(&signer.info()).unwrap(),
+        payer: Signer::try_from(&payer.info()).unwrap(),
+        associated_token_program: Program::try_from(&associated_token_program.info()).unwrap(),
+        token_program: Program::try_from(&token_program.info()).unwrap(),
+        system_program: Program::try_from(&system_program.info()).unwrap(),
+    };
+    warp_to(1_705_000_000);
+
+    let err = christmas::spend_points(
+        Context::new(&crate::ID, &mut accounts, &[], bumps(&["claim_record"])),
+        2,
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::PointsRewardNotSet.into());
+
+    accounts.store_points.reward_cost = 30;
+    christmas::spend_points(
+        Context::new(&crate::ID, &mut accounts, &[], bumps(&["claim_record"])),
+        2,
+    )
+    .unwrap();
+    assert_eq!(
+        token_instructions(&take_cpis()),
+        vec![
+            TokenInstruction::Burn { amount: 60 },
+            TokenInstruction::Transfer { amount: 2 }
+        ]
+    );
+    assert_eq!(accounts.claim_record.claimed, 2);
+    assert_eq!(accounts.claim_record.bump, 254);
+    assert!(accounts.reward_coupon.has_supply);
+
+    // thaws and refreezes `soulbound` reward coupons
+    accounts.reward_coupon.options.soulbound = true;
+    accounts.user_token_account = Account::try_from(&frozen_user_token_account.info()).unwrap();
+    christmas::spend_points(
+        Context::new(&crate::ID, &mut accounts, &[], bumps(&["claim_record"])),
+        1,
+    )
+    .unwrap();
+    assert_eq!(
+        token_instructions(&take_cpis()),
+        vec![
+            TokenInstruction::Burn { amount: 30 },
+            TokenInstruction::ThawAccount,
+            TokenInstruction::Transfer { amount: 1 },
+            TokenInstruction::FreezeAccount
+        ]
+    );
+    accounts.reward_coupon.options.soulbound = false;
+
+    // rewards count towards the claim limit
+    accounts.reward_coupon.options.max_claims_per_user = 4;
+    let err = christmas::spend_points(
+        Context::new(&crate::ID, &mut accounts, &[], bumps(&["claim_record"])),
+        2,
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::ClaimLimitExceeded.into());
+    assert_eq!(accounts.claim_record.claimed, 3);
+    accounts.reward_coupon.options.max_claims_per_user = 0;
+
+    // rewards can't be claimed in disabled regions
+    accounts.config.set_region_enabled(b"SGP", false).unwrap();
+    let err = christmas::spend_points(
+        Context::new(&crate::ID, &mut accounts, &[], bumps(&["claim_record"])),
+        1,
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::RegionDisabled.into());
+    accounts.config.set_region_enabled(b"SGP", true).unwrap();
+
+    // the market can't be overdrawn and the last reward empties it
+    let err = christmas::spend_points(
+        Context::new(&crate::ID, &mut accounts, &[], bumps(&["claim_record"])),
+        11,
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::InsufficientSupply.into());
+    christmas::spend_points(
+        Context::new(&crate::ID, &mut accounts, &[], bumps(&["claim_record"])),
+        10,
+    )
+    .unwrap();
+    assert!(!accounts.reward_coupon.has_supply);
+}
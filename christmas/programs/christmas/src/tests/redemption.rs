@@ -0,0 +1,416 @@
+use super::*;
+
+#[test]
+fn test_redeem_coupon_validity() {
+    let mut fixture = RedeemCouponFixture::new();
+    let mut accounts = fixture.accounts();
+
+    // region is disabled
+    warp_to(1_705_000_000);
+    accounts.config.set_region_enabled(b"SGP", false).unwrap();
+    let err = christmas::redeem_coupon(
+        Context::new(&crate::ID, &mut accounts, &[], RedeemCouponFixture::bumps()),
+        1,
+        0,
+        "".to_string(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::RegionDisabled.into());
+    accounts.config.set_region_enabled(b"SGP", true).unwrap();
+
+    warp_to(1_704_067_199);
+    let err = christmas::redeem_coupon(
+        Context::new(&crate::ID, &mut accounts, &[], RedeemCouponFixture::bumps()),
+        1,
+        0,
+        "".to_string(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::CouponNotYetValid.into());
+
+    warp_to(1_706_745_601);
+    let err = christmas::redeem_coupon(
+        Context::new(&crate::ID, &mut accounts, &[], RedeemCouponFixture::bumps()),
+        1,
+        0,
+        "".to_string(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::CouponExpired.into());
+
+    warp_to(1_706_745_600);
+    christmas::redeem_coupon(
+        Context::new(&crate::ID, &mut accounts, &[], RedeemCouponFixture::bumps()),
+        1,
+        0,
+        "".to_string(),
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_redeem_coupon_cosigned() {
+    let mut store_owner = TestAccount::new_signer();
+    let mut someone_else = TestAccount::new_signer();
+    let mut fixture = RedeemCouponFixture::new();
+    let mut cashier_role = TestAccount::new_account(&StoreRole {
+        store: fixture.store.key,
+        delegate: someone_else.key,
+        permissions: PERMISSION_CREATE_COUPONS,
+        bump: 254,
+        ..new_store_role()
+    });
+
+    let mut accounts = fixture.accounts();
+    accounts.store.owner = store_owner.key;
+    accounts.coupon.options.cosigned_redeem = true;
+    warp_to(1_705_000_000);
+
+    // user can't redeem alone
+    let err = christmas::redeem_coupon(
+        Context::new(&crate::ID, &mut accounts, &[], RedeemCouponFixture::bumps()),
+        1,
+        0,
+        "".to_string(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::StoreSignatureRequired.into());
+
+    // co-signer must be the store owner
+    accounts.store_signer = Some(Signer::try_from(&someone_else.info()).unwrap());
+    let err = christmas::redeem_coupon(
+        Context::new(&crate::ID, &mut accounts, &[], RedeemCouponFixture::bumps()),
+        1,
+        0,
+        "".to_string(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::StoreSignatureRequired.into());
+
+    // co-signer must have permission to verify redemptions
+    accounts.store_signer_role = Some(Account::try_from(&cashier_role.info()).unwrap());
+    let err = christmas::redeem_coupon(
+        Context::new(&crate::ID, &mut accounts, &[], RedeemCouponFixture::bumps()),
+        1,
+        0,
+        "".to_string(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::StoreSignatureRequired.into());
+
+    accounts.store_signer_role.as_mut().unwrap().permissions = PERMISSION_VERIFY_REDEMPTIONS;
+    christmas::redeem_coupon(
+        Context::new(&crate::ID, &mut accounts, &[], RedeemCouponFixture::bumps()),
+        1,
+        0,
+        "".to_string(),
+    )
+    .unwrap();
+
+    accounts.store_signer = Some(Signer::try_from(&store_owner.info()).unwrap());
+    accounts.store_signer_role = None;
+    christmas::redeem_coupon(
+        Context::new(&crate::ID, &mut accounts, &[], RedeemCouponFixture::bumps()),
+        1,
+        0,
+        "".to_string(),
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_redeem_coupon_receipt() {
+    let mut fixture = RedeemCouponFixture::new();
+    let mut accounts = fixture.accounts();
+    warp_to(1_705_000_000);
+
+    let err = christmas::redeem_coupon(
+        Context::new(&crate::ID, &mut accounts, &[], RedeemCouponFixture::bumps()),
+        2,
+        7,
+        "a very very very very long reference".to_string(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::ReferenceTooLong.into());
+
+    christmas::redeem_coupon(
+        Context::new(&crate::ID, &mut accounts, &[], RedeemCouponFixture::bumps()),
+        2,
+        7,
+        "POS-1234".to_string(),
+    )
+    .unwrap();
+    assert_eq!(accounts.redemption.id, 7);
+    assert_eq!(accounts.redemption.coupon, accounts.coupon.key());
+    assert_eq!(accounts.redemption.store, accounts.store.key());
+    assert_eq!(accounts.redemption.user, accounts.signer.key());
+    assert_eq!(accounts.redemption.num_tokens, 2);
+    assert_eq!(accounts.redemption.timestamp, 1_705_000_000_000);
+    assert!(accounts.redemption.reference.starts_with("POS-1234\0"));
+    assert_eq!(accounts.redemption.payer, accounts.payer.key());
+    assert_eq!(accounts.redemption.bump, 254);
+}
+
+#[test]
+fn test_redeem_coupon_soulbound() {
+    let mut fixture = RedeemCouponFixture::new();
+    fixture.user_token_account =
+        TestAccount::new_token_account(fixture.mint.key, fixture.user.key, 10)
+            .freeze_token_account();
+
+    let mut accounts = fixture.accounts();
+    accounts.coupon.options.soulbound = true;
+    warp_to(1_705_000_000);
+
+    christmas::redeem_coupon(
+        Context::new(&crate::ID, &mut accounts, &[], RedeemCouponFixture::bumps()),
+        2,
+        0,
+        String::new(),
+    )
+    .unwrap();
+    assert_eq!(
+        token_instructions(&take_cpis()),
+        vec![
+            TokenInstruction::ThawAccount,
+            TokenInstruction::Burn { amount: 2 },
+            TokenInstruction::FreezeAccount
+        ]
+    );
+}
+
+#[test]
+fn test_redeem_coupon_multi_use() {
+    let mut fixture = RedeemCouponFixture::new();
+    fixture.user_token_account =
+        TestAccount::new_token_account(fixture.mint.key, fixture.user.key, 2);
+    let mut uses = TestAccount::new_account(&new_coupon_uses());
+
+    let mut accounts = fixture.accounts();
+    accounts.coupon.options.uses_per_token = 3;
+    warp_to(1_705_000_000);
+
+    // requires the uses account
+    let err = christmas::redeem_coupon(
+        Context::new(&crate::ID, &mut accounts, &[], RedeemCouponFixture::bumps()),
+        1,
+        0,
+        String::new(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::MissingUsesAccount.into());
+
+    // starts a token without burning it
+    accounts.uses = Some(Account::try_from(&uses.info()).unwrap());
+    christmas::redeem_coupon(
+        Context::new(&crate::ID, &mut accounts, &[], RedeemCouponFixture::bumps()),
+        1,
+        0,
+        String::new(),
+    )
+    .unwrap();
+    assert!(take_cpis().is_empty());
+    assert_eq!(accounts.uses.as_ref().unwrap().remaining, 2);
+    assert_eq!(accounts.uses.as_ref().unwrap().bump, 254);
+
+    // burns the token when it is used up
+    christmas::redeem_coupon(
+        Context::new(&crate::ID, &mut accounts, &[], RedeemCouponFixture::bumps()),
+        2,
+        1,
+        String::new(),
+    )
+    .unwrap();
+    assert_eq!(
+        token_instructions(&take_cpis()),
+        vec![TokenInstruction::Burn { amount: 1 }]
+    );
+    assert_eq!(accounts.uses.as_ref().unwrap().remaining, 0);
+
+    // uses up 1 token and starts the next
+    christmas::redeem_coupon(
+        Context::new(&crate::ID, &mut accounts, &[], RedeemCouponFixture::bumps()),
+        4,
+        2,
+        String::new(),
+    )
+    .unwrap();
+    assert_eq!(
+        token_instructions(&take_cpis()),
+        vec![TokenInstruction::Burn { amount: 1 }]
+    );
+    assert_eq!(accounts.uses.as_ref().unwrap().remaining, 2);
+
+    // needs 3 tokens (2 remaining + 3 + 2 of 3)
+    let err = christmas::redeem_coupon(
+        Context::new(&crate::ID, &mut accounts, &[], RedeemCouponFixture::bumps()),
+        7,
+        3,
+        String::new(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::InsufficientCouponBalance.into());
+}
+
+#[test]
+fn test_redeem_coupon_schedule() {
+    let mut fixture = RedeemCouponFixture::new();
+    let mut accounts = fixture.accounts();
+    // weekend lunch in SGT
+    accounts.coupon.options.schedule = CouponSchedule {
+        weekdays: 0b100_0001,
+        start_minute: 11 * 60,
+        end_minute: 14 * 60,
+        utc_offset_minutes: 8 * 60,
+    };
+
+    // fri 12 jan 2024 03:06 SGT, sat 6 jan 2024 15:30 SGT
+    for timestamp in [1_705_000_000, 1_704_526_200] {
+        warp_to(timestamp);
+        let err = christmas::redeem_coupon(
+            Context::new(&crate::ID, &mut accounts, &[], RedeemCouponFixture::bumps()),
+            1,
+            0,
+            String::new(),
+        )
+        .unwrap_err();
+        assert_eq!(err, ChristmasError::CouponNotInSchedule.into());
+    }
+
+    // sat 6 jan 2024 12:30 SGT
+    warp_to(1_704_515_400);
+    christmas::redeem_coupon(
+        Context::new(&crate::ID, &mut accounts, &[], RedeemCouponFixture::bumps()),
+        1,
+        0,
+        String::new(),
+    )
+    .unwrap();
+
+    // overnight schedules wrap past midnight
+    let schedule = CouponSchedule {
+        weekdays: 0b111_1111,
+        start_minute: 22 * 60,
+        end_minute: 2 * 60,
+        utc_offset_minutes: 0,
+    };
+    assert!(schedule.contains(1_704_585_600_000)); // sun 7 jan 2024 00:00 UTC
+    assert!(!schedule.contains(1_704_628_800_000)); // sun 7 jan 2024 12:00 UTC
+    assert_eq!(
+        CouponSchedule {
+            start_minute: 24 * 60,
+            ..schedule
+        }
+        .validate()
+        .unwrap_err(),
+        ChristmasError::InvalidSchedule.into()
+    );
+
+    // after midnight is part of the previous day's window (saturday night only)
+    let schedule = CouponSchedule {
+        weekdays: 0b100_0000,
+        ..schedule
+    };
+    assert!(schedule.contains(1_704_580_200_000)); // sat 6 jan 2024 22:30 UTC
+    assert!(schedule.contains(1_704_589_200_000)); // sun 7 jan 2024 01:00 UTC
+    assert!(!schedule.contains(1_704_502_800_000)); // sat 6 jan 2024 01:00 UTC
+
+    // windows can end at midnight (but can't be empty)
+    let schedule = CouponSchedule {
+        weekdays: 0b111_1111,
+        start_minute: 18 * 60,
+        end_minute: 24 * 60,
+        utc_offset_minutes: 0,
+    };
+    assert!(schedule.validate().is_ok());
+    assert!(schedule.contains(1_704_671_940_000)); // sun 7 jan 2024 23:59 UTC
+    assert!(!schedule.contains(1_704_672_000_000)); // mon 8 jan 2024 00:00 UTC
+    assert_eq!(
+        CouponSchedule {
+            end_minute: 18 * 60,
+            ..schedule
+        }
+        .validate()
+        .unwrap_err(),
+        ChristmasError::InvalidSchedule.into()
+    );
+}
+
+#[test]
+fn test_close_redemptions_other_store() {
+    let mut signer = TestAccount::new_signer();
+    let mut store = TestAccount::new_account(&Store {
+        owner: signer.key,
+        bump: 254,
+        ..new_store()
+    });
+    let mut payer = TestAccount::new_signer();
+    let mut redemption = TestAccount::new_account(&Redemption {
+        store: Pubkey::new_unique(),
+        payer: payer.key,
+        bump: 254,
+        ..new_redemption()
+    });
+    let remaining_accounts = [redemption.info(), payer.info()];
+
+    let mut accounts = CloseRedemptions {
+        store: Account::try_from(&store.info()).unwrap(),
+        signer: Signer::try_from(&signer.info()).unwrap(),
+    };
+
+    let err = christmas::close_redemptions(Context::new(
+        &crate::ID,
+        &mut accounts,
+        &remaining_accounts,
+        BTreeMap::new(),
+    ))
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::RedemptionStoreMismatch.into());
+    assert_eq!(remaining_accounts[0].lamports(), 1_000_000);
+}
+
+#[test]
+fn test_close_redemptions_refunds_payer() {
+    let mut signer = TestAccount::new_signer();
+    let mut store = TestAccount::new_account(&Store {
+        owner: signer.key,
+        bump: 254,
+        ..new_store()
+    });
+    let mut payer = TestAccount::new_signer();
+    let mut someone_else = TestAccount::new_signer();
+    let mut redemption = TestAccount::new_account(&Redemption {
+        store: store.key,
+        payer: payer.key,
+        bump: 254,
+        ..new_redemption()
+    });
+
+    let mut accounts = CloseRedemptions {
+        store: Account::try_from(&store.info()).unwrap(),
+        signer: Signer::try_from(&signer.info()).unwrap(),
+    };
+
+    let (redemption, payer, someone_else) = (redemption.info(), payer.info(), someone_else.info());
+
+    // rent can only go back to whoever paid for the receipt
+    let err = christmas::close_redemptions(Context::new(
+        &crate::ID,
+        &mut accounts,
+        &[redemption.clone(), someone_else],
+        BTreeMap::new(),
+    ))
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::RedemptionPayerMismatch.into());
+
+    christmas::close_redemptions(Context::new(
+        &crate::ID,
+        &mut accounts,
+        &[redemption.clone(), payer.clone()],
+        BTreeMap::new(),
+    ))
+    .unwrap();
+    assert_eq!(redemption.lamports(), 0);
+    assert!(redemption.data_is_empty());
+    assert_eq!(payer.lamports(), 1_001_000_000);
+}
@@ -0,0 +1,104 @@
+use super::*;
+
+#[test]
+fn test_grant_revoke_role() {
+    let mut owner = TestAccount::new_signer();
+    let mut payer = TestAccount::new_signer();
+    let mut cashier = TestAccount::new_signer();
+    let mut store = TestAccount::new_account(&Store {
+        owner: owner.key,
+        bump: 254,
+        ..new_store()
+    });
+    let mut role = TestAccount::new_account(&new_store_role());
+    let mut cashier_role = TestAccount::new_account(&StoreRole {
+        store: store.key,
+        delegate: cashier.key,
+        permissions: PERMISSION_VERIFY_REDEMPTIONS,
+        bump: 254,
+        ..new_store_role()
+    });
+    let mut delegate = TestAccount::new_signer();
+    let mut system_program = TestAccount::new_program(System::id());
+
+    let mut accounts = GrantRole {
+        store: Account::try_from(&store.info()).unwrap(),
+        role: Account::try_from(&role.info()).unwrap(),
+        delegate: UncheckedAccount::try_from(delegate.info()),
+        signer_role: None,
+        signer: Signer::try_from(&owner.info()).unwrap(),
+        payer: Signer::try_from(&payer.info()).unwrap(),
+        system_program: Program::try_from(&system_program.info()).unwrap(),
+    };
+
+    let err = christmas::grant_role(
+        Context::new(&crate::ID, &mut accounts, &[], bumps(&["role"])),
+        1 << 7,
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::InvalidPermissions.into());
+
+    christmas::grant_role(
+        Context::new(&crate::ID, &mut accounts, &[], bumps(&["role"])),
+        PERMISSION_CREATE_COUPONS | PERMISSION_MINT_TO_MARKET,
+    )
+    .unwrap();
+    assert_eq!(accounts.role.store, accounts.store.key());
+    assert_eq!(accounts.role.delegate, accounts.delegate.key());
+    assert_eq!(
+        accounts.role.permissions,
+        PERMISSION_CREATE_COUPONS | PERMISSION_MINT_TO_MARKET
+    );
+    assert_eq!(accounts.role.bump, 254);
+
+    // staff without `PERMISSION_MANAGE_STAFF` can't grant roles
+    accounts.signer = Signer::try_from(&cashier.info()).unwrap();
+    accounts.signer_role = Some(Account::try_from(&cashier_role.info()).unwrap());
+    let err = christmas::grant_role(
+        Context::new(&crate::ID, &mut accounts, &[], bumps(&["role"])),
+        PERMISSION_ALL,
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::Unauthorized.into());
+
+    // staff managers can't grant permissions they don't hold
+    accounts.signer_role.as_mut().unwrap().permissions =
+        PERMISSION_MANAGE_STAFF | PERMISSION_VERIFY_REDEMPTIONS;
+    let err = christmas::grant_role(
+        Context::new(&crate::ID, &mut accounts, &[], bumps(&["role"])),
+        PERMISSION_VERIFY_REDEMPTIONS | PERMISSION_MINT_TO_MARKET,
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::Unauthorized.into());
+
+    christmas::grant_role(
+        Context::new(&crate::ID, &mut accounts, &[], bumps(&["role"])),
+        PERMISSION_VERIFY_REDEMPTIONS,
+    )
+    .unwrap();
+    assert_eq!(accounts.role.permissions, PERMISSION_VERIFY_REDEMPTIONS);
+
+    let mut accounts = RevokeRole {
+        store: Account::try_from(&store.info()).unwrap(),
+        role: Account::try_from(&role.info()).unwrap(),
+        signer_role: Some(Account::try_from(&cashier_role.info()).unwrap()),
+        signer: Signer::try_from(&cashier.info()).unwrap(),
+    };
+    let err = christmas::revoke_role(Context::new(
+        &crate::ID,
+        &mut accounts,
+        &[],
+        BTreeMap::new(),
+    ))
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::Unauthorized.into());
+
+    accounts.signer_role.as_mut().unwrap().permissions = PERMISSION_MANAGE_STAFF;
+    christmas::revoke_role(Context::new(
+        &crate::ID,
+        &mut accounts,
+        &[],
+        BTreeMap::new(),
+    ))
+    .unwrap();
+}
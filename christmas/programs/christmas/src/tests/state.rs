@@ -0,0 +1,184 @@
+use super::*;
+
+#[test]
+fn test_set_paused() {
+    let mut admin = TestAccount::new_signer();
+    let mut program_state = TestAccount::new_account(&ProgramState {
+        admin: admin.key,
+        ..new_state()
+    });
+
+    let mut accounts = UpdateProgramState {
+        program_state: Account::try_from(&program_state.info()).unwrap(),
+        signer: Signer::try_from(&admin.info()).unwrap(),
+    };
+    christmas::set_paused(
+        Context::new(&crate::ID, &mut accounts, &[], BTreeMap::new()),
+        true,
+    )
+    .unwrap();
+    assert!(accounts.program_state.paused);
+    accounts.program_state.exit(&crate::ID).unwrap();
+
+    // can't create stores while paused
+    let mut store = TestAccount::new_account(&new_store());
+    let mut signer = TestAccount::new_signer();
+    let mut payer = TestAccount::new_signer();
+    let mut system_program = TestAccount::new_program(System::id());
+    let mut config = TestAccount::new_account(&new_config());
+    let mut accounts = CreateStore {
+        store: Account::try_from(&store.info()).unwrap(),
+        signer: Signer::try_from(&signer.info()).unwrap(),
+        payer: Signer::try_from(&payer.info()).unwrap(),
+        state: Account::try_from(&program_state.info()).unwrap(),
+        config: Account::try_from(&config.info()).unwrap(),
+        system_program: Program::try_from(&system_program.info()).unwrap(),
+    };
+    let err = christmas::create_store(
+        Context::new(&crate::ID, &mut accounts, &[], bumps(&["store"])),
+        "store".to_string(),
+        0,
+        *b"SGP",
+        *b"w21z3w",
+        "https://store.com".to_string(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::ProgramPaused.into());
+    assert_eq!(accounts.store.bump, 0);
+}
+
+#[test]
+fn test_set_paused_not_admin() {
+    let admin = Pubkey::new_unique();
+    let mut signer = TestAccount::new_signer();
+    let (state_key, state_bump) = find_pda(&[b"state"]);
+    let mut program_state = TestAccount::new_account_with_key(
+        state_key,
+        &ProgramState {
+            admin,
+            bump: state_bump,
+            ..new_state()
+        },
+    );
+
+    let err = try_accounts::<UpdateProgramState>(&[program_state.info(), signer.info()])
+        .map(|_| ())
+        .unwrap_err();
+    assert_eq!(err, ErrorCode::ConstraintRaw.into());
+
+    signer.key = admin;
+    assert!(try_accounts::<UpdateProgramState>(&[program_state.info(), signer.info()]).is_ok());
+}
+
+#[test]
+fn test_initialize_not_upgrade_authority() {
+    let mut upgrade_authority = TestAccount::new_signer();
+    let mut signer = TestAccount::new_signer();
+    let (state_key, state_bump) = find_pda(&[b"state"]);
+    let mut program_state = TestAccount::new_account_with_key(
+        state_key,
+        &ProgramState {
+            bump: state_bump,
+            ..new_state()
+        },
+    );
+    program_state.lamports = Rent::default().minimum_balance(program_state.data().len());
+    let (config_key, config_bump) = find_pda(&[b"config"]);
+    let mut config = TestAccount::new_account_with_key(
+        config_key,
+        &Config {
+            bump: config_bump,
+            ..new_config()
+        },
+    );
+    config.lamports = Rent::default().minimum_balance(config.data().len());
+    let (mut program, mut program_data) =
+        TestAccount::new_upgradeable_program(crate::ID, upgrade_authority.key);
+    let mut system_program = TestAccount::new_program(System::id());
+    warp_to(1_705_000_000); // `init_if_needed` reads the rent sysvar
+
+    let err = try_accounts::<Initialize>(&[
+        program_state.info(),
+        config.info(),
+        program.info(),
+        program_data.info(),
+        signer.info(),
+        system_program.info(),
+    ])
+    .map(|_| ())
+    .unwrap_err();
+    assert_eq!(err, ErrorCode::ConstraintRaw.into());
+
+    assert!(try_accounts::<Initialize>(&[
+        program_state.info(),
+        config.info(),
+        program.info(),
+        program_data.info(),
+        upgrade_authority.info(),
+        system_program.info(),
+    ])
+    .is_ok());
+}
+
+#[test]
+fn test_migrate_program_state() {
+    let mut upgrade_authority = TestAccount::new_signer();
+    let (state_key, state_bump) = find_pda(&[b"state"]);
+    let mut program_state = TestAccount::new_account_with_key(state_key, &new_state());
+    // program state created before the admin was added
+    let mut legacy = program_state.data()[..DISCRIMINATOR_SIZE].to_vec();
+    legacy.push(1); // is_initialized
+    legacy.extend_from_slice(&5u64.to_le_bytes()); // store_counter
+    legacy.push(state_bump);
+    program_state.data_mut()[..legacy.len()].copy_from_slice(&legacy);
+    program_state.info().realloc(legacy.len(), false).unwrap();
+    program_state.lamports = Rent::default().minimum_balance(legacy.len());
+    let (mut program, mut program_data) =
+        TestAccount::new_upgradeable_program(crate::ID, upgrade_authority.key);
+    let mut system_program = TestAccount::new_program(System::id());
+    warp_to(1_705_000_000);
+
+    let mut accounts = try_accounts::<MigrateProgramState>(&[
+        program_state.info(),
+        program.info(),
+        program_data.info(),
+        upgrade_authority.info(),
+        system_program.info(),
+    ])
+    .unwrap();
+    christmas::migrate_program_state(Context::new(
+        &crate::ID,
+        &mut accounts,
+        &[],
+        BTreeMap::new(),
+    ))
+    .unwrap();
+    let cpis = take_cpis();
+    assert_eq!(
+        cpis[0],
+        solana_program::system_instruction::transfer(
+            &accounts.signer.key(),
+            &state_key,
+            Rent::default().minimum_balance(ProgramState::len())
+                - Rent::default().minimum_balance(legacy.len())
+        )
+    );
+
+    let migrated = Account::<ProgramState>::try_from(&accounts.program_state).unwrap();
+    assert!(migrated.is_initialized);
+    assert_eq!(migrated.store_counter, 5);
+    assert_eq!(migrated.active_stores, 5);
+    assert_eq!(migrated.admin, accounts.signer.key());
+    assert!(!migrated.paused);
+    assert_eq!(migrated.bump, state_bump);
+
+    // only once
+    let err = christmas::migrate_program_state(Context::new(
+        &crate::ID,
+        &mut accounts,
+        &[],
+        BTreeMap::new(),
+    ))
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::AlreadyMigrated.into());
+}
@@ -0,0 +1,374 @@
+use super::*;
+
+#[test]
+fn test_create_store_twice() {
+    let mut store = TestAccount::new_account(&new_store());
+    let mut signer = TestAccount::new_signer();
+    let mut payer = TestAccount::new_signer();
+    let mut state = TestAccount::new_account(&new_state());
+    let mut system_program = TestAccount::new_program(System::id());
+
+    let mut config = TestAccount::new_account(&new_config());
+    let mut accounts = CreateStore {
+        store: Account::try_from(&store.info()).unwrap(),
+        signer: Signer::try_from(&signer.info()).unwrap(),
+        payer: Signer::try_from(&payer.info()).unwrap(),
+        state: Account::try_from(&state.info()).unwrap(),
+        config: Account::try_from(&config.info()).unwrap(),
+        system_program: Program::try_from(&system_program.info()).unwrap(),
+    };
+
+    christmas::create_store(
+        Context::new(&crate::ID, &mut accounts, &[], bumps(&["store"])),
+        "store".to_string(),
+        0,
+        *b"SGP",
+        *b"w21z3w",
+        "https://store.com".to_string(),
+    )
+    .unwrap();
+    assert_eq!(accounts.state.store_counter, 1);
+    assert_eq!(accounts.state.active_stores, 1);
+
+    let err = christmas::create_store(
+        Context::new(&crate::ID, &mut accounts, &[], bumps(&["store"])),
+        "overwritten".to_string(),
+        0,
+        *b"USA",
+        *b"w21z98",
+        "https://overwritten.com".to_string(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::StoreAlreadyExists.into());
+    assert!(accounts.store.name.starts_with("store\0"));
+    assert!(accounts.store.uri.starts_with("https://store.com"));
+    assert_eq!(accounts.store.region, *b"SGP");
+    assert_eq!(accounts.store.geohash, *b"w21z3w");
+    assert_eq!(accounts.state.store_counter, 1);
+    assert_eq!(accounts.state.active_stores, 1);
+}
+
+#[test]
+fn test_close_store() {
+    let mut signer = TestAccount::new_signer();
+    let mut store = TestAccount::new_account(&Store {
+        owner: signer.key,
+        live_coupons: 1,
+        bump: 254,
+        ..new_store()
+    });
+    let mut treasury = TestAccount::new_empty();
+    let mut state = TestAccount::new_account(&ProgramState {
+        store_counter: 2,
+        active_stores: 2,
+        ..new_state()
+    });
+
+    let mut accounts = CloseStore {
+        store: Account::try_from(&store.info()).unwrap(),
+        treasury: UncheckedAccount::try_from(treasury.info()),
+        signer: Signer::try_from(&signer.info()).unwrap(),
+        state: Account::try_from(&state.info()).unwrap(),
+    };
+
+    let err = christmas::close_store(Context::new(
+        &crate::ID,
+        &mut accounts,
+        &[],
+        BTreeMap::new(),
+    ))
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::StoreHasLiveCoupons.into());
+    assert_eq!(accounts.state.active_stores, 2);
+
+    accounts.store.live_coupons = 0;
+    accounts.state.paused = true;
+    let err = christmas::close_store(Context::new(
+        &crate::ID,
+        &mut accounts,
+        &[],
+        BTreeMap::new(),
+    ))
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::ProgramPaused.into());
+
+    accounts.state.paused = false;
+    christmas::close_store(Context::new(
+        &crate::ID,
+        &mut accounts,
+        &[],
+        BTreeMap::new(),
+    ))
+    .unwrap();
+    assert_eq!(accounts.state.store_counter, 2);
+    assert_eq!(accounts.state.active_stores, 1);
+    assert_eq!(accounts.signer.lamports(), 1_000_000_000);
+}
+
+#[test]
+fn test_close_store_funded_treasury() {
+    let mut signer = TestAccount::new_signer();
+    let mut store = TestAccount::new_account(&Store {
+        owner: signer.key,
+        bump: 254,
+        ..new_store()
+    });
+    let mut treasury = TestAccount::new_account(&Treasury {
+        store: store.key,
+        bump: 254,
+    });
+    treasury.lamports = Rent::default().minimum_balance(treasury.data().len()) + 500;
+    let treasury_lamports = treasury.lamports;
+    let price_mint = Pubkey::new_unique();
+    let mut funded_token_account = TestAccount::new_token_account(price_mint, treasury.key, 100);
+    let mut empty_token_account = TestAccount::new_token_account(price_mint, treasury.key, 0);
+    let empty_token_account = empty_token_account.info();
+    let mut other_token_account = TestAccount::new_token_account(price_mint, signer.key, 0);
+    let mut state = TestAccount::new_account(&ProgramState {
+        active_stores: 1,
+        ..new_state()
+    });
+
+    let mut accounts = CloseStore {
+        store: Account::try_from(&store.info()).unwrap(),
+        treasury: UncheckedAccount::try_from(treasury.info()),
+        signer: Signer::try_from(&signer.info()).unwrap(),
+        state: Account::try_from(&state.info()).unwrap(),
+    };
+
+    // token balances must be withdrawn first
+    let err = christmas::close_store(Context::new(
+        &crate::ID,
+        &mut accounts,
+        &[empty_token_account.clone(), funded_token_account.info()],
+        BTreeMap::new(),
+    ))
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::TreasuryNotEmpty.into());
+
+    // token accounts must belong to the treasury
+    let err = christmas::close_store(Context::new(
+        &crate::ID,
+        &mut accounts,
+        &[other_token_account.info()],
+        BTreeMap::new(),
+    ))
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::InvalidPaymentAccounts.into());
+
+    christmas::close_store(Context::new(
+        &crate::ID,
+        &mut accounts,
+        &[empty_token_account],
+        BTreeMap::new(),
+    ))
+    .unwrap();
+    assert_eq!(
+        accounts.signer.lamports(),
+        1_000_000_000 + treasury_lamports
+    );
+    assert_eq!(accounts.treasury.lamports(), 0);
+    assert!(accounts.treasury.data_is_empty());
+    assert_eq!(*accounts.treasury.owner, System::id());
+}
+
+#[test]
+fn test_transfer_store_owner() {
+    let mut owner = TestAccount::new_signer();
+    let mut new_owner = TestAccount::new_signer();
+    let (owner_key, new_owner_key) = (owner.key, new_owner.key);
+    let mut store = TestAccount::new_account(&Store {
+        owner: owner.key,
+        bump: 254,
+        ..new_store()
+    });
+    let manager = Pubkey::new_unique();
+    let mut manager_role = TestAccount::new_account(&StoreRole {
+        store: store.key,
+        delegate: manager,
+        permissions: PERMISSION_ALL,
+        bump: 254,
+        ..new_store_role()
+    });
+
+    let mut accounts = ProposeStoreOwner {
+        store: Account::try_from(&store.info()).unwrap(),
+        signer: Signer::try_from(&owner.info()).unwrap(),
+    };
+    christmas::propose_store_owner(
+        Context::new(&crate::ID, &mut accounts, &[], BTreeMap::new()),
+        new_owner_key,
+    )
+    .unwrap();
+    assert_eq!(accounts.store.pending_owner, new_owner_key);
+    assert_eq!(accounts.store.owner, owner_key);
+    accounts.store.exit(&crate::ID).unwrap();
+
+    let mut accounts = AcceptStoreOwner {
+        store: Account::try_from(&store.info()).unwrap(),
+        signer: Signer::try_from(&new_owner.info()).unwrap(),
+    };
+    christmas::accept_store_owner(Context::new(
+        &crate::ID,
+        &mut accounts,
+        &[],
+        BTreeMap::new(),
+    ))
+    .unwrap();
+    assert_eq!(accounts.store.owner, new_owner_key);
+    assert_eq!(accounts.store.pending_owner, Pubkey::default());
+
+    // roles granted by the previous owner are void
+    let manager_role = Some(Account::try_from(&manager_role.info()).unwrap());
+    assert_eq!(accounts.store.role_epoch, 1);
+    assert!(!is_authorized(
+        &accounts.store,
+        manager,
+        &manager_role,
+        PERMISSION_MANAGE_STAFF
+    ));
+}
+
+#[test]
+fn test_accept_store_owner_not_pending() {
+    let mut owner = TestAccount::new_signer();
+    let mut new_owner = TestAccount::new_signer();
+    let mut store = TestAccount::new_account(&Store {
+        owner: owner.key,
+        pending_owner: new_owner.key,
+        bump: 254,
+        ..new_store()
+    });
+
+    // the current owner can't accept on behalf of the proposed owner
+    let err = try_accounts::<AcceptStoreOwner>(&[store.info(), owner.info()])
+        .map(|_| ())
+        .unwrap_err();
+    assert_eq!(err, ErrorCode::ConstraintRaw.into());
+
+    assert!(try_accounts::<AcceptStoreOwner>(&[store.info(), new_owner.info()]).is_ok());
+}
+
+#[test]
+fn test_update_store() {
+    let mut signer = TestAccount::new_signer();
+    let mut store = TestAccount::new_account(&Store {
+        name: "store".to_string(),
+        region: *b"SGP",
+        geohash: *b"w21z3w",
+        owner: signer.key,
+        bump: 254,
+        ..new_store()
+    });
+
+    let mut config = TestAccount::new_account(&new_config());
+    let mut accounts = UpdateStore {
+        store: Account::try_from(&store.info()).unwrap(),
+        config: Account::try_from(&config.info()).unwrap(),
+        signer: Signer::try_from(&signer.info()).unwrap(),
+    };
+
+    // invalid inputs leave the store unchanged
+    let err = christmas::update_store(
+        Context::new(&crate::ID, &mut accounts, &[], BTreeMap::new()),
+        "renamed".to_string(),
+        *b"XYZ",
+        *b"w21z98",
+        "".to_string(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::InvalidRegion.into());
+    assert_eq!(accounts.store.region, *b"SGP");
+
+    let err = christmas::update_store(
+        Context::new(&crate::ID, &mut accounts, &[], BTreeMap::new()),
+        "a very very very very very very long store name".to_string(),
+        *b"USA",
+        *b"w21z98",
+        "".to_string(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::NameTooLong.into());
+    assert_eq!(accounts.store.name, "store");
+
+    christmas::update_store(
+        Context::new(&crate::ID, &mut accounts, &[], BTreeMap::new()),
+        "renamed".to_string(),
+        *b"USA",
+        *b"w21z98",
+        "https://renamed.com".to_string(),
+    )
+    .unwrap();
+    assert!(accounts.store.name.starts_with("renamed\0"));
+    assert!(accounts.store.uri.starts_with("https://renamed.com"));
+    assert_eq!(accounts.store.region, *b"USA");
+    assert_eq!(accounts.store.geohash, *b"w21z98");
+}
+
+#[test]
+fn test_migrate_store() {
+    let mut admin = TestAccount::new_signer();
+    let owner = Pubkey::new_unique();
+    let name = pad_string(
+        "store",
+        STORE_NAME_SIZE - STRING_PREFIX_SIZE,
+        ChristmasError::NameTooLong,
+    )
+    .unwrap();
+    let uri = pad_string(
+        "",
+        URI_SIZE - STRING_PREFIX_SIZE,
+        ChristmasError::UriTooLong,
+    )
+    .unwrap();
+    let mut store = TestAccount::new_account(&Store {
+        name: name.clone(),
+        uri: uri.clone(),
+        ..new_store()
+    });
+    // stores created before `live_coupons` and `pending_owner` were added
+    let mut legacy = store.data()[..DISCRIMINATOR_SIZE].to_vec();
+    (3u64, name.clone(), *b"SGP", *b"w21z3w", uri, owner, 254u8)
+        .serialize(&mut legacy)
+        .unwrap();
+    assert_eq!(legacy.len(), Store::legacy_len());
+    store.data_mut()[..legacy.len()].copy_from_slice(&legacy);
+    store.info().realloc(legacy.len(), false).unwrap();
+    let mut state = TestAccount::new_account(&ProgramState {
+        admin: admin.key,
+        bump: 254,
+        ..new_state()
+    });
+    let mut system_program = TestAccount::new_program(System::id());
+
+    let mut accounts = MigrateStore {
+        store: UncheckedAccount::try_from(store.info()),
+        state: Account::try_from(&state.info()).unwrap(),
+        signer: Signer::try_from(&admin.info()).unwrap(),
+        system_program: Program::try_from(&system_program.info()).unwrap(),
+    };
+    warp_to(1_705_000_000);
+
+    christmas::migrate_store(
+        Context::new(&crate::ID, &mut accounts, &[], BTreeMap::new()),
+        2,
+    )
+    .unwrap();
+    let migrated = Account::<Store>::try_from(&accounts.store).unwrap();
+    assert_eq!(migrated.id, 3);
+    assert_eq!(migrated.name, name);
+    assert_eq!(migrated.region, *b"SGP");
+    assert_eq!(migrated.geohash, *b"w21z3w");
+    assert_eq!(migrated.owner, owner);
+    assert_eq!(migrated.live_coupons, 2);
+    assert_eq!(migrated.pending_owner, Pubkey::default());
+    assert_eq!(migrated.bump, 254);
+
+    // only once
+    let err = christmas::migrate_store(
+        Context::new(&crate::ID, &mut accounts, &[], BTreeMap::new()),
+        2,
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::AlreadyMigrated.into());
+}
@@ -0,0 +1,46 @@
+use super::*;
+
+#[test]
+fn test_withdraw_treasury() {
+    let mut signer = TestAccount::new_signer();
+    let mut store = TestAccount::new_account(&Store {
+        owner: signer.key,
+        bump: 254,
+        ..new_store()
+    });
+    let mut treasury = TestAccount::new_account(&Treasury {
+        store: store.key,
+        bump: 254,
+    });
+    let rent = Rent::default().minimum_balance(treasury.data().len());
+    treasury.lamports = rent + 500;
+    let mut token_program = TestAccount::new_program(anchor_spl::token::ID);
+
+    let mut state = TestAccount::new_account(&new_state());
+    let mut accounts = WithdrawTreasury {
+        store: Account::try_from(&store.info()).unwrap(),
+        treasury: Account::try_from(&treasury.info()).unwrap(),
+        treasury_token_account: None,
+        destination_token_account: None,
+        state: Account::try_from(&state.info()).unwrap(),
+        signer: Signer::try_from(&signer.info()).unwrap(),
+        token_program: Program::try_from(&token_program.info()).unwrap(),
+    };
+    warp_to(1_705_000_000);
+
+    // can't withdraw the rent
+    let err = christmas::withdraw_treasury(
+        Context::new(&crate::ID, &mut accounts, &[], BTreeMap::new()),
+        501,
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::InsufficientTreasuryBalance.into());
+
+    christmas::withdraw_treasury(
+        Context::new(&crate::ID, &mut accounts, &[], BTreeMap::new()),
+        500,
+    )
+    .unwrap();
+    assert_eq!(accounts.treasury.to_account_info().lamports(), rent);
+    assert_eq!(accounts.signer.lamports(), 1_000_000_500);
+}
@@ -0,0 +1,35 @@
+use super::*;
+
+#[test]
+fn test_create_user_twice() {
+    let mut user = TestAccount::new_account(&new_user());
+    let mut payer = TestAccount::new_signer();
+    let mut signer = TestAccount::new_signer();
+    let mut system_program = TestAccount::new_program(System::id());
+
+    let mut config = TestAccount::new_account(&new_config());
+    let mut accounts = CreateUser {
+        user: Account::try_from(&user.info()).unwrap(),
+        config: Account::try_from(&config.info()).unwrap(),
+        payer: Signer::try_from(&payer.info()).unwrap(),
+        signer: Signer::try_from(&signer.info()).unwrap(),
+        system_program: Program::try_from(&system_program.info()).unwrap(),
+    };
+
+    christmas::create_user(
+        Context::new(&crate::ID, &mut accounts, &[], bumps(&["user"])),
+        *b"SGP",
+        "https://user.com".to_string(),
+    )
+    .unwrap();
+
+    let err = christmas::create_user(
+        Context::new(&crate::ID, &mut accounts, &[], bumps(&["user"])),
+        *b"USA",
+        "https://other.com".to_string(),
+    )
+    .unwrap_err();
+    assert_eq!(err, ChristmasError::UserAlreadyExists.into());
+    assert_eq!(accounts.user.region, *b"SGP");
+    assert!(accounts.user.uri.starts_with("https://user.com"));
+}
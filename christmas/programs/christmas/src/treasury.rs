@@ -0,0 +1,46 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token::{Token, TokenAccount};
+
+use crate::defs::*;
+use crate::state::ProgramState;
+use crate::store::Store;
+
+#[derive(Accounts)]
+pub struct WithdrawTreasury<'info> {
+    #[account(
+        constraint = store.owner == signer.key(), // only the owner can withdraw
+    )]
+    pub store: Account<'info, Store>,
+    #[account(
+        mut,
+        seeds = [b"treasury", store.key().as_ref()],
+        bump = treasury.bump,
+    )]
+    pub treasury: Account<'info, Treasury>,
+    // required to withdraw SPL tokens (else lamports are withdrawn to the signer)
+    #[account(mut, token::authority = treasury)]
+    pub treasury_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(mut)]
+    pub destination_token_account: Option<Account<'info, TokenAccount>>,
+    #[account(
+        seeds = [b"state"],
+        bump = state.bump,
+    )]
+    pub state: Account<'info, ProgramState>, // checked for `paused`
+    #[account(mut)]
+    pub signer: Signer<'info>,
+    pub token_program: Program<'info, Token>,
+}
+
+// Holds payments for paid coupons (lamports in the account, SPL tokens in its token accounts)
+#[account]
+pub struct Treasury {
+    pub store: Pubkey,
+    pub bump: u8,
+}
+
+impl Treasury {
+    pub fn len() -> usize {
+        DISCRIMINATOR_SIZE + PUBKEY_SIZE + BUMP_SIZE
+    }
+}
@@ -1,5 +1,6 @@
 use anchor_lang::prelude::*;
 
+use crate::config::Config;
 use crate::defs::*;
 
 #[derive(Accounts)]
@@ -12,6 +13,11 @@ pub struct CreateUser<'info> {
         space = User::len(),
     )]
     pub user: Account<'info, User>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>, // checked for allowed regions
     #[account(mut)]
     pub payer: Signer<'info>,
     pub signer: Signer<'info>,
@@ -26,6 +32,11 @@ pub struct UpdateUser<'info> {
         bump
     )]
     pub user: Account<'info, User>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>, // checked for allowed regions
     #[account(mut)]
     pub payer: Signer<'info>,
     pub signer: Signer<'info>, // can only update own account
@@ -1,6 +1,9 @@
+use anchor_lang::prelude::*;
 use geohash::{decode, encode, neighbor, Coord, Direction};
 use std::collections::HashSet;
 
+use crate::errors::ChristmasError;
+
 fn degrees_to_km(latitude: f64, longitude: f64) -> (f64, f64) {
     // Mean radius of the Earth in kilometers
     const EARTH_RADIUS_KM: f64 = 6371.0;
@@ -81,6 +84,14 @@ pub fn code_bytes_to_country(code_bytes: &[u8; 3]) -> Option<String> {
     return code_to_country(&code);
 }
 
+pub fn validate_region(code_bytes: &[u8; 3]) -> Result<()> {
+    require!(
+        code_bytes_to_country(code_bytes).is_some(),
+        ChristmasError::InvalidRegion
+    );
+    Ok(())
+}
+
 pub fn code_to_country(code: &str) -> Option<String> {
     match code {
         "@@@" => Some("@@@".to_string()),
@@ -380,4 +391,18 @@ mod tests {
 
         assert!(around_singapore.eq(&geohashes));
     }
+
+    #[test]
+    fn test_validate_region() {
+        assert!(validate_region(b"SGP").is_ok());
+        assert!(validate_region(b"@@@").is_ok());
+        assert_eq!(
+            validate_region(b"XYZ").unwrap_err(),
+            ChristmasError::InvalidRegion.into()
+        );
+        assert_eq!(
+            validate_region(&[0, 0, 0]).unwrap_err(),
+            ChristmasError::InvalidRegion.into()
+        );
+    }
 }
@@ -1,10 +1,75 @@
+use anchor_lang::prelude::*;
+use anchor_lang::system_program;
+
 use crate::defs::{DATE_HASH_BITS, DATE_HASH_SIZE, DAYS_SINCE_1_JAN_2024, MS_PER_DAY};
+use crate::errors::ChristmasError;
 
-pub fn pad_string(s: &str, length: usize) -> String {
-    assert!(s.len() <= length); // string should not be longer than length
+pub fn pad_string(s: &str, length: usize, error: ChristmasError) -> Result<String> {
+    // string should not be longer than length
+    if s.len() > length {
+        return Err(error.into());
+    }
     let zeros = vec![0u8; length - s.len()];
 
-    return s.to_owned() + std::str::from_utf8(&zeros).unwrap();
+    return Ok(s.to_owned() + &String::from_utf8_lossy(&zeros));
+}
+
+// Grows an account created with an older (smaller) layout, topping up its rent from `payer`
+pub fn realloc_with_rent<'info>(
+    account: &AccountInfo<'info>,
+    len: usize,
+    payer: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+) -> Result<()> {
+    let rent = Rent::get()?
+        .minimum_balance(len)
+        .saturating_sub(account.lamports());
+    if rent > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                system_program.clone(),
+                system_program::Transfer {
+                    from: payer.clone(),
+                    to: account.clone(),
+                },
+            ),
+            rent,
+        )?;
+    }
+    account.realloc(len, true)?;
+    Ok(())
+}
+
+pub fn checked_add_supply(supply: u32, num_tokens: u64) -> Result<u32> {
+    // dont allow more than u32 tokens
+    let num_tokens: u32 = num_tokens
+        .try_into()
+        .map_err(|_| ChristmasError::SupplyOverflow)?;
+
+    return supply
+        .checked_add(num_tokens)
+        .ok_or(ChristmasError::SupplyOverflow.into());
+}
+
+pub fn checked_sub_supply(supply: u32, num_tokens: u64) -> Result<u32> {
+    let num_tokens: u32 = num_tokens
+        .try_into()
+        .map_err(|_| ChristmasError::InsufficientSupply)?;
+
+    return supply
+        .checked_sub(num_tokens)
+        .ok_or(ChristmasError::InsufficientSupply.into());
+}
+
+pub fn current_timestamp_ms() -> Result<u64> {
+    // coupon dates are stored in milliseconds (see `epoch_days_from_date`)
+    let unix_timestamp = Clock::get()?.unix_timestamp;
+    return Ok((unix_timestamp.max(0) as u64).saturating_mul(1000));
+}
+
+pub fn validate_date_range(valid_from: u64, valid_to: u64) -> Result<()> {
+    require!(valid_from <= valid_to, ChristmasError::InvalidDateRange);
+    Ok(())
 }
 
 pub fn epoch_days_from_date(date: u64) -> u64 {
@@ -47,6 +112,64 @@ mod tests {
 
     use super::*;
 
+    #[test]
+    fn test_pad_string() {
+        assert_eq!(
+            pad_string("abc", 5, ChristmasError::NameTooLong).unwrap(),
+            "abc\0\0"
+        );
+        assert_eq!(
+            pad_string("abcde", 5, ChristmasError::NameTooLong).unwrap(),
+            "abcde"
+        );
+        assert_eq!(
+            pad_string("abcdef", 5, ChristmasError::NameTooLong).unwrap_err(),
+            ChristmasError::NameTooLong.into()
+        );
+        assert_eq!(
+            pad_string("abcdef", 5, ChristmasError::UriTooLong).unwrap_err(),
+            ChristmasError::UriTooLong.into()
+        );
+    }
+
+    #[test]
+    fn test_checked_add_supply() {
+        assert_eq!(checked_add_supply(0, 10).unwrap(), 10);
+        assert_eq!(checked_add_supply(u32::MAX - 1, 1).unwrap(), u32::MAX);
+        assert_eq!(
+            checked_add_supply(u32::MAX, 1).unwrap_err(),
+            ChristmasError::SupplyOverflow.into()
+        );
+        assert_eq!(
+            checked_add_supply(0, u32::MAX as u64 + 1).unwrap_err(),
+            ChristmasError::SupplyOverflow.into()
+        );
+    }
+
+    #[test]
+    fn test_checked_sub_supply() {
+        assert_eq!(checked_sub_supply(10, 3).unwrap(), 7);
+        assert_eq!(checked_sub_supply(10, 10).unwrap(), 0);
+        assert_eq!(
+            checked_sub_supply(10, 11).unwrap_err(),
+            ChristmasError::InsufficientSupply.into()
+        );
+        assert_eq!(
+            checked_sub_supply(10, u32::MAX as u64 + 1).unwrap_err(),
+            ChristmasError::InsufficientSupply.into()
+        );
+    }
+
+    #[test]
+    fn test_validate_date_range() {
+        assert!(validate_date_range(1, 1).is_ok());
+        assert!(validate_date_range(1, 2).is_ok());
+        assert_eq!(
+            validate_date_range(2, 1).unwrap_err(),
+            ChristmasError::InvalidDateRange.into()
+        );
+    }
+
     #[test]
     fn test_u8_to_byte_mask() {
         assert!(u8_to_byte_mask(0).eq(&0x00));
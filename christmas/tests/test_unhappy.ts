@@ -1,5 +1,8 @@
 import { web3 } from "@coral-xyz/anchor";
 import * as anchor from "@coral-xyz/anchor";
+import {
+    createAssociatedTokenAccountIdempotentInstruction,
+} from "@solana/spl-token";
 import { cleanString, stringToUint8Array } from "../app/src/lib/utils";
 
 import chai from "chai";
@@ -163,6 +166,55 @@ describe("Test Unhappy", () => {
             }
         }
 
+        // out of validity period coupons can't be claimed or redeemed
+        const [buyerUserPda, _] = web3.PublicKey.findProgramAddressSync(
+            [
+                anchor.utils.bytes.utf8.encode("user"),
+                buyerKeypair.publicKey.toBuffer(),
+            ],
+            buyerClient.programId
+        );
+        for (const [coupon, supply, balance] of mintedCoupons) {
+            const name = cleanString(coupon.account.name);
+            if (!["after", "before"].includes(name)) {
+                continue;
+            }
+            const error =
+                name === "after"
+                    ? "Coupon is not yet valid"
+                    : "Coupon has expired";
+
+            await expect(
+                buyerClient.claimFromMarket({
+                    mint: coupon.account.mint,
+                    numTokens: 1,
+                })
+            ).to.be.rejectedWith(error);
+
+            // the token account must exist to reach the validity check
+            await web3.sendAndConfirmTransaction(
+                buyerClient.connection,
+                new web3.Transaction().add(
+                    createAssociatedTokenAccountIdempotentInstruction(
+                        buyerKeypair.publicKey,
+                        await buyerClient.getUserTokenAccount(
+                            coupon.account.mint
+                        ),
+                        buyerUserPda,
+                        coupon.account.mint
+                    )
+                ),
+                [buyerKeypair]
+            );
+            await expect(
+                buyerClient.redeemCoupon({
+                    coupon: coupon.publicKey,
+                    mint: coupon.account.mint,
+                    numTokens: 1,
+                })
+            ).to.be.rejectedWith(error);
+        }
+
         // check does not return out of validity coupons
         const marketCoupons = await sellerClient.getCoupons({
             region,